@@ -0,0 +1,48 @@
+// Copyright (C) 2025  Antonio-Miguel Corbi Bellot
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use rand::Rng;
+use std::hint::black_box;
+use weasel_rs::libweasel::{charset::Charset, rng};
+
+/// How `rand_char` used to work: count the chars of a string and walk it
+/// up to the chosen one.
+fn rand_char_by_nth<R: Rng + ?Sized>(s: &str, rng: &mut R) -> char {
+    let i = (rng.random::<u64>() as usize) % s.chars().count();
+
+    s.chars().nth(i).unwrap()
+}
+
+fn rand_char(c: &mut Criterion) {
+    for name in ["weasel", "latin1", "unicode"] {
+        let cs = Charset::from_name(name).unwrap();
+        let s: String = cs.chars().collect();
+        let mut group = c.benchmark_group(format!("rand_char/{name} ({} chars)", cs.len()));
+
+        let mut rng = rng::from_seed(0);
+        group.bench_function("indexed", |b| b.iter(|| cs.rand_char(black_box(&mut rng))));
+
+        let mut rng = rng::from_seed(0);
+        group.bench_function("string nth", |b| {
+            b.iter(|| rand_char_by_nth(black_box(&s), &mut rng))
+        });
+
+        group.finish();
+    }
+}
+
+criterion_group!(benches, rand_char);
+criterion_main!(benches);
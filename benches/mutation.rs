@@ -0,0 +1,42 @@
+// Copyright (C) 2025  Antonio-Miguel Corbi Bellot
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use std::hint::black_box;
+use weasel_rs::libweasel::{
+    mutation::{Bernoulli, GeometricSkip, MutationOperator},
+    rng,
+};
+
+fn positions(c: &mut Criterion) {
+    for (n, mr) in [(100, 0.05), (100_000, 0.0001)] {
+        let mut group = c.benchmark_group(format!("positions/{n} genes, mr {mr}"));
+
+        let mut rng = rng::from_seed(0);
+        group.bench_function("bernoulli", |b| {
+            b.iter(|| Bernoulli.positions(black_box(n), mr, &mut rng))
+        });
+
+        let mut rng = rng::from_seed(0);
+        group.bench_function("geometric skip", |b| {
+            b.iter(|| GeometricSkip.positions(black_box(n), mr, &mut rng))
+        });
+
+        group.finish();
+    }
+}
+
+criterion_group!(benches, positions);
+criterion_main!(benches);
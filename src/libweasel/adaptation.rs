@@ -0,0 +1,276 @@
+// Copyright (C) 2025  Antonio-Miguel Corbi Bellot
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use crate::libweasel::allele::Gaussian;
+use rand::Rng;
+
+/// Adapted mutation rates never leave [MIN_MR, MAX_MR].
+pub const MIN_MR: f64 = 1e-4;
+pub const MAX_MR: f64 = 1.0;
+
+// -- Classes: ------------------------------------------------------------
+
+/// How the mutation rate changes along a run.  A generation is a success
+/// when some child is fitter than the best parent.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum MrAdaptation {
+    /// The rate given by `with_mr`, for the whole run.
+    #[default]
+    Fixed,
+    /// The 1/5th success rule: every `window` generations, the rate is
+    /// divided by `factor` if more than a fifth of the children were
+    /// successful, and multiplied by it if less.
+    OneFifth { window: u32, factor: f64 },
+    /// Rechenberg-style steps after every generation: the rate is
+    /// multiplied by `factor` on success and by `factor^(-1/4)` on
+    /// failure, so it stays put at a success rate of 1/5.
+    Rechenberg { factor: f64 },
+    /// Every child carries its own rate, the one of its parent times
+    /// `exp(tau * N(0, 1))`, and mutates with it.  Good rates survive
+    /// along with the genes they produced.
+    SelfAdaptive { tau: f64 },
+}
+
+/// The state of an adaptation along a run.
+#[derive(Clone, Debug)]
+pub struct MrAdapter {
+    adaptation: MrAdaptation,
+    /// Lowest rate the adaptation may reach
+    min_mr: f64,
+    /// Generations since the last 1/5th rule update
+    generations: u32,
+    /// Successful children of those generations
+    successes: u32,
+    /// Children of those generations
+    trials: u32,
+}
+
+// -- Impl. blocks: -------------------------------------------------------
+
+impl MrAdaptation {
+    /// Names accepted by `from_name`.
+    pub const NAMES: [&'static str; 4] = ["fixed", "one-fifth", "rechenberg", "self-adaptive"];
+
+    /// The 1/5th rule with a window of 10 generations and a factor of 0.85.
+    pub fn one_fifth() -> Self {
+        MrAdaptation::OneFifth {
+            window: 10,
+            factor: 0.85,
+        }
+    }
+
+    pub fn rechenberg() -> Self {
+        MrAdaptation::Rechenberg { factor: 1.5 }
+    }
+
+    pub fn self_adaptive() -> Self {
+        MrAdaptation::SelfAdaptive { tau: 0.3 }
+    }
+
+    /// One of the adaptations from its name.  The `window` of one-fifth,
+    /// the `factor` of rechenberg and the `tau` of self-adaptive can follow
+    /// a colon, e.g. `one-fifth:20` or `self-adaptive:0.5`.  A factor must
+    /// be above 1 and a tau can't be negative.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let (name, param) = match name.split_once(':') {
+            Some((n, p)) => (n, Some(p)),
+            None => (name, None),
+        };
+
+        match (name, param) {
+            ("fixed", None) => Some(MrAdaptation::Fixed),
+            ("one-fifth", None) => Some(MrAdaptation::one_fifth()),
+            ("one-fifth", Some(w)) => Some(MrAdaptation::OneFifth {
+                window: w.parse::<u32>().ok()?.max(1),
+                factor: 0.85,
+            }),
+            ("rechenberg", None) => Some(MrAdaptation::rechenberg()),
+            ("rechenberg", Some(f)) => match f.parse::<f64>().ok()? {
+                factor if factor.is_finite() && factor > 1.0 => {
+                    Some(MrAdaptation::Rechenberg { factor })
+                }
+                _ => None,
+            },
+            ("self-adaptive", None) => Some(MrAdaptation::self_adaptive()),
+            ("self-adaptive", Some(t)) => match t.parse::<f64>().ok()? {
+                tau if tau.is_finite() && tau >= 0.0 => Some(MrAdaptation::SelfAdaptive { tau }),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            MrAdaptation::Fixed => "fixed",
+            MrAdaptation::OneFifth { .. } => "one-fifth",
+            MrAdaptation::Rechenberg { .. } => "rechenberg",
+            MrAdaptation::SelfAdaptive { .. } => "self-adaptive",
+        }
+    }
+
+    /// The state of a run whose rate never goes below `min_mr`, nor
+    /// below `MIN_MR`.  Below one mutation per chromosome, most children
+    /// are mere copies of their parents, so a good floor is 1 / its length.
+    pub fn adapter(&self, min_mr: f64) -> MrAdapter {
+        MrAdapter {
+            adaptation: *self,
+            min_mr: min_mr.clamp(MIN_MR, MAX_MR),
+            generations: 0,
+            successes: 0,
+            trials: 0,
+        }
+    }
+}
+
+impl MrAdapter {
+    /// `mr` within [min_mr, MAX_MR], and `min_mr` if it's NaN or infinite,
+    /// which `clamp` would let through.
+    fn bounded(&self, mr: f64) -> f64 {
+        match mr.is_finite() {
+            true => mr.clamp(self.min_mr, MAX_MR),
+            false => self.min_mr,
+        }
+    }
+
+    /// The rate a child mutates with, its parent's one being `mr`.
+    pub fn child_mr<R: Rng + ?Sized>(&self, mr: f64, rng: &mut R) -> f64 {
+        match self.adaptation {
+            MrAdaptation::SelfAdaptive { tau } => {
+                self.bounded(mr * (tau * Gaussian::standard_normal(rng)).exp())
+            }
+            _ => mr,
+        }
+    }
+
+    /// The rate of the next generation, `successes` of the `trials`
+    /// children of this one having been fitter than the best parent.
+    pub fn adapt(&mut self, mr: f64, successes: u32, trials: u32) -> f64 {
+        match self.adaptation {
+            MrAdaptation::Fixed | MrAdaptation::SelfAdaptive { .. } => mr,
+            MrAdaptation::OneFifth { window, factor } => {
+                self.generations += 1;
+                self.successes += successes;
+                self.trials += trials;
+                if self.generations < window {
+                    return mr;
+                }
+
+                let ratio = self.successes as f64 / self.trials.max(1) as f64;
+                self.generations = 0;
+                self.successes = 0;
+                self.trials = 0;
+
+                let mr = if ratio > 0.2 {
+                    mr / factor
+                } else if ratio < 0.2 {
+                    mr * factor
+                } else {
+                    mr
+                };
+                self.bounded(mr)
+            }
+            MrAdaptation::Rechenberg { factor } => {
+                let mr = if successes > 0 {
+                    mr * factor
+                } else {
+                    mr * factor.powf(-0.25)
+                };
+                self.bounded(mr)
+            }
+        }
+    }
+}
+
+// -- Tests: --------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::libweasel::rng;
+
+    #[test]
+    fn one_fifth() {
+        let mut a = MrAdaptation::OneFifth {
+            window: 2,
+            factor: 0.5,
+        }
+        .adapter(0.0);
+
+        assert_eq!(a.adapt(0.1, 5, 10), 0.1);
+        assert_eq!(a.adapt(0.1, 5, 10), 0.2);
+        assert_eq!(a.adapt(0.2, 0, 10), 0.2);
+        assert_eq!(a.adapt(0.2, 1, 10), 0.1);
+        assert_eq!(a.adapt(0.1, 1, 10), 0.1);
+        assert_eq!(a.adapt(0.1, 3, 10), 0.1);
+    }
+
+    #[test]
+    fn rechenberg() {
+        let mut a = MrAdaptation::Rechenberg { factor: 16.0 }.adapter(0.0);
+
+        assert_eq!(a.adapt(0.01, 1, 10), 0.16);
+        assert_eq!(a.adapt(0.16, 0, 10), 0.08);
+        assert_eq!(a.adapt(0.5, 3, 10), MAX_MR);
+        assert_eq!(a.adapt(MIN_MR, 0, 10), MIN_MR);
+
+        let mut a = MrAdaptation::rechenberg().adapter(0.05);
+        assert_eq!(a.adapt(0.05, 0, 10), 0.05);
+
+        // Not allowed by from_name, but never NaN anyway.
+        let mut a = MrAdaptation::Rechenberg { factor: -1.0 }.adapter(0.05);
+        assert_eq!(a.adapt(0.1, 0, 10), 0.05);
+        assert_eq!(a.adapt(f64::NAN, 1, 10), 0.05);
+    }
+
+    #[test]
+    fn self_adaptive() {
+        let mut rng = rng::from_seed(0);
+        let mut sa = MrAdaptation::self_adaptive().adapter(0.05);
+        let rates: Vec<f64> = (0..100).map(|_| sa.child_mr(0.1, &mut rng)).collect();
+
+        assert!(rates.iter().any(|&r| r > 0.1) && rates.iter().any(|&r| r < 0.1));
+        assert!(rates.iter().all(|&r| (0.05..=MAX_MR).contains(&r)));
+        assert_eq!(sa.adapt(0.1, 10, 10), 0.1);
+
+        let nan = MrAdaptation::SelfAdaptive { tau: f64::NAN }.adapter(0.05);
+        assert_eq!(nan.child_mr(0.1, &mut rng), 0.05);
+
+        let fixed = MrAdaptation::Fixed.adapter(0.0);
+        assert_eq!(fixed.child_mr(0.1, &mut rng), 0.1);
+    }
+
+    #[test]
+    fn by_name() {
+        for name in MrAdaptation::NAMES {
+            assert_eq!(MrAdaptation::from_name(name).unwrap().name(), name);
+        }
+        assert_eq!(
+            MrAdaptation::from_name("self-adaptive:0.5"),
+            Some(MrAdaptation::SelfAdaptive { tau: 0.5 })
+        );
+        assert!(MrAdaptation::from_name("rechenberg:x").is_none());
+        for bad in [
+            "rechenberg:-1",
+            "rechenberg:1",
+            "rechenberg:nan",
+            "rechenberg:inf",
+            "self-adaptive:nan",
+            "self-adaptive:-0.1",
+        ] {
+            assert!(MrAdaptation::from_name(bad).is_none(), "{bad}");
+        }
+        assert!(MrAdaptation::from_name("nope").is_none());
+    }
+}
@@ -0,0 +1,209 @@
+// Copyright (C) 2025  Antonio-Miguel Corbi Bellot
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use crate::libweasel::charset::Charset;
+use rand::Rng;
+use std::f64::consts::TAU;
+use std::fmt;
+use std::ops::RangeInclusive;
+
+// -- Traits: -------------------------------------------------------------
+
+/// The value held by a gene.  Every allele type has a `Domain` describing
+/// the values it can take and how they are drawn: a `Charset` for chars,
+/// a range for integers, a `Gaussian` for reals...
+pub trait Allele: Clone + PartialEq + fmt::Debug + Send + Sync + 'static {
+    type Domain: fmt::Debug + Send + Sync;
+
+    /// Printed between two genes.
+    const SEPARATOR: &'static str = " ";
+
+    fn random<R: Rng + ?Sized>(domain: &Self::Domain, rng: &mut R) -> Self;
+    /// What this allele turns into when its gene mutates.
+    fn mutate<R: Rng + ?Sized>(&self, domain: &Self::Domain, rng: &mut R) -> Self;
+    fn in_domain(&self, domain: &Self::Domain) -> bool;
+    /// How a gene holding this allele is printed.
+    fn symbol(&self) -> String;
+}
+
+// -- Classes: ------------------------------------------------------------
+
+/// Reals in [lo, hi].  A mutation adds normal noise of deviation `sigma`
+/// and clamps the result back into the interval.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Gaussian {
+    lo: f64,
+    hi: f64,
+    sigma: f64,
+}
+
+// -- Impl. blocks: -------------------------------------------------------
+
+impl Allele for char {
+    type Domain = Charset;
+
+    const SEPARATOR: &'static str = "";
+
+    fn random<R: Rng + ?Sized>(cs: &Charset, rng: &mut R) -> Self {
+        cs.rand_char(rng)
+    }
+
+    fn mutate<R: Rng + ?Sized>(&self, cs: &Charset, rng: &mut R) -> Self {
+        cs.mutate_char(*self, rng)
+    }
+
+    fn in_domain(&self, cs: &Charset) -> bool {
+        cs.contains(*self)
+    }
+
+    fn symbol(&self) -> String {
+        self.to_string()
+    }
+}
+
+/// Bit strings: a mutation flips the bit.
+impl Allele for bool {
+    type Domain = ();
+
+    const SEPARATOR: &'static str = "";
+
+    fn random<R: Rng + ?Sized>(_: &(), rng: &mut R) -> Self {
+        rng.random_bool(0.5)
+    }
+
+    fn mutate<R: Rng + ?Sized>(&self, _: &(), _rng: &mut R) -> Self {
+        !self
+    }
+
+    fn in_domain(&self, _: &()) -> bool {
+        true
+    }
+
+    fn symbol(&self) -> String {
+        if *self { "1" } else { "0" }.to_string()
+    }
+}
+
+/// Bounded integers: a mutation draws a new value of the range.
+macro_rules! integer_allele {
+    ($($t:ty),*) => {
+        $(
+            impl Allele for $t {
+                type Domain = RangeInclusive<$t>;
+
+                fn random<R: Rng + ?Sized>(range: &RangeInclusive<$t>, rng: &mut R) -> Self {
+                    rng.random_range(range.clone())
+                }
+
+                fn mutate<R: Rng + ?Sized>(&self, range: &RangeInclusive<$t>, rng: &mut R) -> Self {
+                    rng.random_range(range.clone())
+                }
+
+                fn in_domain(&self, range: &RangeInclusive<$t>) -> bool {
+                    range.contains(self)
+                }
+
+                fn symbol(&self) -> String {
+                    self.to_string()
+                }
+            }
+        )*
+    };
+}
+
+integer_allele!(u8, u16, u32, i32, i64);
+
+impl Gaussian {
+    pub fn new(lo: f64, hi: f64, sigma: f64) -> Self {
+        Gaussian {
+            lo: lo.min(hi),
+            hi: hi.max(lo),
+            sigma: sigma.abs(),
+        }
+    }
+
+    /// A sample of the standard normal distribution (Box-Muller).
+    pub(crate) fn standard_normal<R: Rng + ?Sized>(rng: &mut R) -> f64 {
+        let u1 = 1.0 - rng.random::<f64>();
+        let u2 = rng.random::<f64>();
+
+        (-2.0 * u1.ln()).sqrt() * (TAU * u2).cos()
+    }
+}
+
+impl Allele for f64 {
+    type Domain = Gaussian;
+
+    fn random<R: Rng + ?Sized>(g: &Gaussian, rng: &mut R) -> Self {
+        rng.random_range(g.lo..=g.hi)
+    }
+
+    fn mutate<R: Rng + ?Sized>(&self, g: &Gaussian, rng: &mut R) -> Self {
+        (self + g.sigma * Gaussian::standard_normal(rng)).clamp(g.lo, g.hi)
+    }
+
+    fn in_domain(&self, g: &Gaussian) -> bool {
+        (g.lo..=g.hi).contains(self)
+    }
+
+    fn symbol(&self) -> String {
+        format!("{self:.3}")
+    }
+}
+
+// -- Tests: --------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::libweasel::rng;
+
+    #[test]
+    fn bool_flips() {
+        let mut rng = rng::from_seed(0);
+
+        assert!(!true.mutate(&(), &mut rng));
+        assert!(false.mutate(&(), &mut rng));
+        assert_eq!(true.symbol(), "1");
+    }
+
+    #[test]
+    fn integers_stay_in_range() {
+        let mut rng = rng::from_seed(1);
+        let range = -3..=3;
+
+        for _ in 0..100 {
+            let a = i32::random(&range, &mut rng);
+            assert!(a.in_domain(&range));
+            assert!(a.mutate(&range, &mut rng).in_domain(&range));
+        }
+        assert!(!7.in_domain(&range));
+    }
+
+    #[test]
+    fn gaussian() {
+        let mut rng = rng::from_seed(2);
+        let g = Gaussian::new(-1.0, 1.0, 0.1);
+
+        let steps: Vec<f64> = (0..1000).map(|_| 0.0f64.mutate(&g, &mut rng)).collect();
+        let mean = steps.iter().sum::<f64>() / steps.len() as f64;
+        let var = steps.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / steps.len() as f64;
+
+        assert!(mean.abs() < 0.02);
+        assert!((var.sqrt() - 0.1).abs() < 0.02);
+
+        let wide = Gaussian::new(-1.0, 1.0, 10.0);
+        assert!((0..100).all(|_| 0.99f64.mutate(&wide, &mut rng).in_domain(&wide)));
+    }
+}
@@ -13,66 +13,539 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
-use std::env;
+use crate::libweasel::adaptation::MrAdaptation;
+use crate::libweasel::charset::Charset;
+use crate::libweasel::config::RunConfig;
+use crate::libweasel::population::Crossover;
+use crate::libweasel::selection::Replacement;
+use crate::libweasel::trace::TraceFormat;
+use crate::libweasel::{distribution, fitness, mutation, selection};
+use clap::builder::NonEmptyStringValueParser;
+use clap::error::ErrorKind;
+use clap::parser::ValueSource;
+use clap::{ArgMatches, Args, CommandFactory, FromArgMatches, Parser, Subcommand};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
 
+/// Evolve a random string into a target sentence, as in Dawkins' weasel
+/// program, or run a genetic algorithm on a population of them.
+#[derive(Clone, Debug, Parser)]
+#[command(
+    name = "weasel-rs",
+    version,
+    args_conflicts_with_subcommands = true,
+    after_help = "Exit status: 0 on success, 1 if the run can't be set up \
+                  (e.g. the sentence has chars outside the charset), \
+                  2 on bad arguments or a bad --config file."
+)]
+pub struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+    /// The arguments of `run`, which is the command if none is given
+    #[command(flatten)]
+    run: Arguments,
+}
+
+/// The modes of the program.
+#[derive(Clone, Debug, Subcommand)]
+pub enum Command {
+    /// Evolve the sentence, the default when no command is given
+    Run(Arguments),
+    /// Time several runs of the same setup, each with its own seed
+    Bench(BenchArguments),
+    /// Run the same setup over a range of mutation rates
+    Sweep(SweepArguments),
+    /// Evolve the run of a seed again, showing some of its generations
+    Replay(ReplayArguments),
+    /// List the charset presets, or check a sentence against a charset
+    #[command(subcommand)]
+    Charset(CharsetCommand),
+    /// Print what theory expects from a run
+    Explain(Arguments),
+}
+
+#[derive(Clone, Debug, Subcommand)]
+pub enum CharsetCommand {
+    /// The presets, with their chars
+    List,
+    /// Whether every char of a sentence is in a charset
+    Validate {
+        #[arg(value_parser = NonEmptyStringValueParser::new())]
+        sentence: String,
+        /// Charset preset, `target` for the chars of the sentence, or a file
+        #[arg(short = 'a', long)]
+        charset: Option<String>,
+    },
+}
+
+#[derive(Clone, Debug, Args)]
+pub struct BenchArguments {
+    #[command(flatten)]
+    run: Arguments,
+    /// Number of runs, seeded from --seed onwards
+    #[arg(long, default_value_t = 10, value_parser = clap::value_parser!(u32).range(1..))]
+    runs: u32,
+}
+
+#[derive(Clone, Debug, Args)]
+pub struct SweepArguments {
+    #[command(flatten)]
+    run: Arguments,
+    /// Lowest mutation rate
+    #[arg(long, value_name = "RATE", default_value_t = 0.01, value_parser = rate)]
+    from: f64,
+    /// Highest mutation rate
+    #[arg(long, value_name = "RATE", default_value_t = 0.2, value_parser = rate)]
+    to: f64,
+    /// Mutation rates tried, evenly spaced
+    #[arg(long, default_value_t = 10, value_parser = clap::value_parser!(u32).range(1..))]
+    steps: u32,
+    /// Runs per mutation rate, seeded from --seed onwards
+    #[arg(long, default_value_t = 5, value_parser = clap::value_parser!(u32).range(1..))]
+    runs: u32,
+}
+
+#[derive(Clone, Debug, Args)]
+pub struct ReplayArguments {
+    /// The run to replay, --seed being required
+    #[command(flatten)]
+    run: Arguments,
+    /// First generation shown
+    #[arg(long = "from", value_name = "GENERATION", default_value_t = 1)]
+    from_generation: u32,
+    /// Last generation shown, the run stops there
+    #[arg(long = "to", value_name = "GENERATION")]
+    to_generation: Option<u32>,
+}
+
+/// The arguments of a run.
+#[derive(Clone, Debug, Args)]
 pub struct Arguments {
+    /// TOML file describing the run, keyed by the long names of these
+    /// flags; flags given too override its values
+    #[arg(long, value_name = "FILE")]
+    config: Option<PathBuf>,
+    /// The target sentence
+    #[arg(short, long = "sentence", value_name = "SENTENCE", default_value = "Me thinks it's like a weasel",
+          value_parser = NonEmptyStringValueParser::new())]
     s: String,
+    /// Mutation rate, in [0, 1]
+    #[arg(short, long = "mrate", value_name = "RATE", default_value_t = 0.08, value_parser = rate)]
     mr: f64,
+    /// Children per generation (λ)
+    #[arg(short, long, visible_alias = "lambda", default_value_t = 500,
+          value_parser = clap::value_parser!(u32).range(1..))]
     ncopies: u32,
+    /// Print one plain record per generation, `generation<TAB>fitness<TAB>genes`,
+    /// with non-ASCII chars escaped as \u{..}; the rest goes to stderr
+    #[arg(short = 'd', long)]
     encoded: bool,
+    /// Print a trace of every generation as `text`, `jsonl` or `csv`;
+    /// the rest goes to stderr
+    #[arg(long, conflicts_with = "encoded", value_parser = format_name)]
+    format: Option<String>,
+    /// Seed of the run, a random one if not given
+    #[arg(short = 'r', long)]
+    seed: Option<u64>,
+    /// Fitness function
+    #[arg(short, long, value_parser = fitness_name)]
+    fitness: Option<String>,
+    /// Charset preset, `target` for the chars of the sentence, or a file
+    #[arg(short = 'a', long)]
+    charset: Option<String>,
+    /// How genes mutate over the charset, uniformly if not given
+    #[arg(long, value_parser = distribution_name)]
+    distribution: Option<String>,
+    /// Evolve a variable-length string, starting with this many genes
+    #[arg(short = 'l', long = "length")]
+    initial_length: Option<usize>,
+    /// Run a genetic algorithm on a population of this size
+    #[arg(short, long, value_parser = positive)]
+    population: Option<usize>,
+    /// Crossover of the genetic algorithm
+    #[arg(short = 'x', long, value_parser = crossover_name)]
+    crossover: Option<String>,
+    /// Selection, optionally followed by `:param`
+    #[arg(short = 't', long, value_parser = selection_name)]
+    selection: Option<String>,
+    /// Parents per generation (μ)
+    #[arg(short = 'u', long, default_value_t = 1, value_parser = positive)]
+    mu: usize,
+    /// `comma` or `plus`
+    #[arg(short = 'c', long, value_parser = replacement_name)]
+    replacement: Option<String>,
+    /// Best parents that always survive
+    #[arg(short, long, default_value_t = 0)]
+    elitism: usize,
+    /// Genes matching the target stop mutating
+    #[arg(long)]
+    latching: bool,
+    /// How the mutation rate changes along the run
+    #[arg(long, value_parser = adaptation_name)]
+    mr_adaptation: Option<String>,
+    /// Mutation operator, optionally followed by `:param`
+    #[arg(long, value_parser = mutation_name)]
+    mutation: Option<String>,
+    /// Stop after this many generations
+    #[arg(short = 'g', long)]
+    max_generations: Option<u32>,
+    /// Stop after this many seconds
+    #[arg(long, value_parser = seconds)]
+    timeout: Option<Duration>,
+    /// Stop on this fitness or better
+    #[arg(long, default_value_t = 0)]
+    target_fitness: u32,
+    /// Stop after this many generations without improvement, 0 never does
+    #[arg(long)]
+    stagnation: Option<u32>,
 }
 
 impl Default for Arguments {
     fn default() -> Self {
-        Arguments {
-            s: String::from("Me thinks it's like a weasel"),
-            mr: 0.08,
-            ncopies: 500,
-            encoded: false,
-        }
+        Cli::parse_from(["weasel-rs"]).run
     }
 }
 
-impl Arguments {
+/// A mutation rate, in [0, 1].
+fn rate(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(r) if (0.0..=1.0).contains(&r) => Ok(r),
+        Ok(r) => Err(format!("{r} is not in [0, 1]")),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn positive(s: &str) -> Result<usize, String> {
+    match s.parse::<usize>() {
+        Ok(0) => Err("it must be at least 1".into()),
+        Ok(n) => Ok(n),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// A positive number of seconds a `Duration` can hold.
+fn seconds(s: &str) -> Result<Duration, String> {
+    match s.parse::<f64>() {
+        Ok(t) if t > 0.0 => match Duration::try_from_secs_f64(t) {
+            Ok(d) if !d.is_zero() => Ok(d),
+            Ok(_) => Err(format!("{s} is less than a nanosecond")),
+            Err(e) => Err(format!("{s}: {e}")),
+        },
+        Ok(_) => Err(format!("{s} is not a positive number of seconds")),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// `v` checked by `parse` as if given as a flag, the error naming `key`.
+fn checked<T: ToString, U>(
+    key: &str,
+    v: &T,
+    parse: fn(&str) -> Result<U, String>,
+) -> Result<U, String> {
+    parse(&v.to_string()).map_err(|e| format!("{key}: {e}"))
+}
+
+fn non_empty(s: &str) -> Result<String, String> {
+    match s {
+        "" => Err("a value is required".into()),
+        s => Ok(s.to_owned()),
+    }
+}
+
+/// Checks the parameter of `s`, if it is `name:param`: it must parse as a
+/// `T` that is `ok`, which `what` describes.
+fn check_param<T: FromStr + Copy>(
+    s: &str,
+    name: &str,
+    ok: impl Fn(T) -> bool,
+    what: &str,
+) -> Result<(), String> {
+    match s.split_once(':') {
+        Some((n, p)) if n == name => match p.parse::<T>() {
+            Ok(v) if ok(v) => Ok(()),
+            _ => Err(format!("the parameter of {name} must be {what}, not {p:?}")),
+        },
+        _ => Ok(()),
+    }
+}
+
+/// `name` if `known`, else an error listing the `names` to use.
+fn check_name(name: &str, known: bool, names: &[&str]) -> Result<String, String> {
+    if known {
+        Ok(name.to_owned())
+    } else {
+        Err(format!("unknown name, use one of: {}", names.join(", ")))
+    }
+}
+
+fn fitness_name(s: &str) -> Result<String, String> {
+    check_name(s, fitness::from_name(s).is_some(), &fitness::FITNESS_NAMES)
+}
+
+fn distribution_name(s: &str) -> Result<String, String> {
+    check_param(
+        s,
+        "keyboard",
+        |p: f64| (0.0..=1.0).contains(&p),
+        "in [0, 1]",
+    )?;
+    check_param(s, "drift", |k: u32| k >= 1, "an integer of at least 1")?;
+    let known = distribution::from_name(s, &Charset::default()).is_some();
+    check_name(s, known, &distribution::DISTRIBUTION_NAMES)
+}
+
+fn crossover_name(s: &str) -> Result<String, String> {
+    check_name(s, Crossover::from_name(s).is_some(), &Crossover::NAMES)
+}
+
+fn selection_name(s: &str) -> Result<String, String> {
+    check_param(
+        s,
+        "tournament",
+        |k: usize| k >= 1,
+        "an integer of at least 1",
+    )?;
+    check_param(s, "rank", |p: f64| (1.0..=2.0).contains(&p), "in [1, 2]")?;
+    check_param(s, "truncation", |f: f64| f > 0.0 && f <= 1.0, "in (0, 1]")?;
+    check_name(
+        s,
+        selection::from_name(s).is_some(),
+        &selection::SELECTION_NAMES,
+    )
+}
+
+fn replacement_name(s: &str) -> Result<String, String> {
+    check_name(s, Replacement::from_name(s).is_some(), &Replacement::NAMES)
+}
+
+fn adaptation_name(s: &str) -> Result<String, String> {
+    check_param(s, "one-fifth", |w: u32| w >= 1, "an integer of at least 1")?;
+    check_param(
+        s,
+        "rechenberg",
+        |f: f64| f > 1.0 && f.is_finite(),
+        "a number above 1",
+    )?;
+    check_param(
+        s,
+        "self-adaptive",
+        |t: f64| t >= 0.0 && t.is_finite(),
+        "a number of at least 0",
+    )?;
+    check_name(
+        s,
+        MrAdaptation::from_name(s).is_some(),
+        &MrAdaptation::NAMES,
+    )
+}
+
+fn format_name(s: &str) -> Result<String, String> {
+    check_name(s, TraceFormat::from_name(s).is_some(), &TraceFormat::NAMES)
+}
+
+fn mutation_name(s: &str) -> Result<String, String> {
+    check_param(s, "exactly", |k: usize| k >= 1, "an integer of at least 1")?;
+    check_name(
+        s,
+        mutation::from_name(s).is_some(),
+        &mutation::MUTATION_NAMES,
+    )
+}
+
+impl Cli {
+    /// The arguments of the program.  On bad ones, or on `--help` and
+    /// `--version`, it prints a message and exits.
     pub fn from_app_args() -> Self {
-        let mut arguments = Self::default();
-        // The first argument (index 0) is always the path used to execute the program.
-        let args: Vec<String> = env::args().collect();
-        let mut argsiter = args.iter();
-
-        // 2. The total number of arguments
-        let num_args = args.len();
-
-        if num_args > 1 {
-            for (i, a) in argsiter.enumerate() {
-                match a.as_str() {
-                    "-s" | "--sentence" => {
-                        let s = args.get(i + 1);
-                        arguments.set_sentence(s.unwrap());
-                    }
-                    "-m" | "--mrate" => {
-                        let m = args.get(i + 1).unwrap();
-                        let m = m.parse::<f64>().ok().unwrap();
-                        arguments.set_mr(m);
-                    }
-                    "-n" | "--ncopies" => {
-                        let nc = args.get(i + 1).unwrap();
-                        let nc = nc.parse::<u32>().ok().unwrap();
-                        arguments.set_ncopies(nc);
-                    }
-                    "-d" | "--encoded" => {
-                        arguments.set_encoded(true);
-                    }
-                    &_ => (),
-                }
-            }
+        Self::try_from_args(std::env::args_os()).unwrap_or_else(|e| e.exit())
+    }
+
+    /// Like `from_app_args`, for the given `args`, returning the error
+    /// instead of exiting.  The `--config` file, if any, is loaded too.
+    pub fn try_from_args<I, T>(args: I) -> Result<Self, clap::Error>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<OsString> + Clone,
+    {
+        let matches = Self::command().try_get_matches_from(args)?;
+        let mut cli = Self::from_arg_matches(&matches)?;
+
+        cli.load_config(&matches)
+            .map_err(|e| Self::command().error(ErrorKind::ValueValidation, e))?;
+        Ok(cli)
+    }
+
+    /// Take the values of the `--config` file not given as flags.  Bench
+    /// and sweep print a summary only, so they skip the output keys.
+    fn load_config(&mut self, matches: &ArgMatches) -> Result<(), String> {
+        let sub = matches.subcommand().map_or(matches, |(_, m)| m);
+        let summary = matches!(self.command, Some(Command::Bench(_) | Command::Sweep(_)));
+        let (args, m) = match &mut self.command {
+            None => (&mut self.run, matches),
+            Some(Command::Run(a) | Command::Explain(a)) => (a, sub),
+            Some(Command::Bench(b)) => (&mut b.run, sub),
+            Some(Command::Sweep(s)) => (&mut s.run, sub),
+            Some(Command::Replay(r)) => (&mut r.run, sub),
+            Some(Command::Charset(_)) => return Ok(()),
+        };
+        let Some(path) = args.config.clone() else {
+            return Ok(());
+        };
+        let config = RunConfig::from_file(&path).map_err(|e| e.to_string())?;
+
+        args.merge_config(&config, |id| {
+            (summary && matches!(id, "encoded" | "format"))
+                || m.value_source(id) == Some(ValueSource::CommandLine)
+        })
+        .map_err(|e| format!("{}: {e}", path.display()))
+    }
+
+    /// The command to carry out, `run` if none was given.
+    pub fn into_command(self) -> Command {
+        self.command.unwrap_or(Command::Run(self.run))
+    }
+}
+
+impl BenchArguments {
+    pub fn run(&self) -> &Arguments {
+        &self.run
+    }
+
+    pub fn runs(&self) -> u32 {
+        self.runs
+    }
+}
+
+impl SweepArguments {
+    pub fn run(&self) -> &Arguments {
+        &self.run
+    }
+
+    /// The mutation rates to try, from `from` to `to`.
+    pub fn rates(&self) -> Vec<f64> {
+        match self.steps {
+            1 => vec![self.from],
+            n => (0..n)
+                .map(|i| self.from + (self.to - self.from) * i as f64 / (n - 1) as f64)
+                .collect(),
         }
+    }
 
-        arguments
+    pub fn runs(&self) -> u32 {
+        self.runs
     }
+}
 
-    pub fn set_sentence(&mut self, s: &str) {
-        self.s = String::from(s);
+impl ReplayArguments {
+    pub fn run(&self) -> &Arguments {
+        &self.run
+    }
+
+    pub fn from_generation(&self) -> u32 {
+        self.from_generation
+    }
+
+    pub fn to_generation(&self) -> Option<u32> {
+        self.to_generation
+    }
+}
+
+impl Arguments {
+    /// Take the values of `c` for the arguments not `given` on the command
+    /// line, `given` being called with the ids of the fields.  They are
+    /// checked as the flags are.
+    pub fn merge_config(
+        &mut self,
+        c: &RunConfig,
+        given: impl Fn(&str) -> bool,
+    ) -> Result<(), String> {
+        let take = |id: &str| !given(id);
+
+        if let Some(v) = c.sentence.as_ref().filter(|_| take("s")) {
+            self.s = checked("sentence", v, non_empty)?;
+        }
+        if let Some(v) = c.mrate.filter(|_| take("mr")) {
+            self.mr = checked("mrate", &v, rate)?;
+        }
+        if let Some(v) = c.ncopies.filter(|_| take("ncopies")) {
+            self.ncopies = checked("ncopies", &v, positive)? as u32;
+        }
+        // A format on the command line overrides `encoded` in the file,
+        // and `-d` a format.
+        if let Some(v) = c.encoded.filter(|_| take("encoded") && !given("format")) {
+            self.encoded = v;
+        }
+        if let Some(v) = c
+            .format
+            .as_ref()
+            .filter(|_| take("format") && !given("encoded"))
+        {
+            self.format = Some(checked("format", v, format_name)?);
+        }
+        if self.encoded && self.format.is_some() {
+            return Err("encoded and format can't be used together".into());
+        }
+        if let Some(v) = c.seed.filter(|_| take("seed")) {
+            self.seed = Some(v);
+        }
+        if let Some(v) = c.fitness.as_ref().filter(|_| take("fitness")) {
+            self.fitness = Some(checked("fitness", v, fitness_name)?);
+        }
+        if let Some(v) = c.charset.as_ref().filter(|_| take("charset")) {
+            self.charset = Some(v.clone());
+        }
+        if let Some(v) = c.distribution.as_ref().filter(|_| take("distribution")) {
+            self.distribution = Some(checked("distribution", v, distribution_name)?);
+        }
+        if let Some(v) = c.length.filter(|_| take("initial_length")) {
+            self.initial_length = Some(v);
+        }
+        if let Some(v) = c.population.filter(|_| take("population")) {
+            self.population = Some(checked("population", &v, positive)?);
+        }
+        if let Some(v) = c.crossover.as_ref().filter(|_| take("crossover")) {
+            self.crossover = Some(checked("crossover", v, crossover_name)?);
+        }
+        if let Some(v) = c.selection.as_ref().filter(|_| take("selection")) {
+            self.selection = Some(checked("selection", v, selection_name)?);
+        }
+        if let Some(v) = c.mu.filter(|_| take("mu")) {
+            self.mu = checked("mu", &v, positive)?;
+        }
+        if let Some(v) = c.replacement.as_ref().filter(|_| take("replacement")) {
+            self.replacement = Some(checked("replacement", v, replacement_name)?);
+        }
+        if let Some(v) = c.elitism.filter(|_| take("elitism")) {
+            self.elitism = v;
+        }
+        if let Some(v) = c.latching.filter(|_| take("latching")) {
+            self.latching = v;
+        }
+        if let Some(v) = c.mr_adaptation.as_ref().filter(|_| take("mr_adaptation")) {
+            self.mr_adaptation = Some(checked("mr-adaptation", v, adaptation_name)?);
+        }
+        if let Some(v) = c.mutation.as_ref().filter(|_| take("mutation")) {
+            self.mutation = Some(checked("mutation", v, mutation_name)?);
+        }
+        if let Some(v) = c.max_generations.filter(|_| take("max_generations")) {
+            self.max_generations = Some(v);
+        }
+        if let Some(v) = c.timeout.filter(|_| take("timeout")) {
+            self.timeout = Some(checked("timeout", &v, seconds)?);
+        }
+        if let Some(v) = c.target_fitness.filter(|_| take("target_fitness")) {
+            self.target_fitness = v;
+        }
+        if let Some(v) = c.stagnation.filter(|_| take("stagnation")) {
+            self.stagnation = Some(v);
+        }
+        Ok(())
+    }
+
+    /// The TOML file the run was described in, if any.
+    pub fn config(&self) -> Option<&Path> {
+        self.config.as_deref()
     }
 
     pub fn sentence(&self) -> &str {
@@ -87,19 +560,268 @@ impl Arguments {
         self.ncopies
     }
 
+    /// Whether generations are printed as plain records instead of in color.
     pub fn encoded(&self) -> bool {
         self.encoded
     }
 
+    /// Name of the trace format, `None` for the colored output.
+    pub fn format(&self) -> Option<&str> {
+        self.format.as_deref()
+    }
+
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    /// Name of the fitness function, `None` to keep the chromosome's default.
+    pub fn fitness(&self) -> Option<&str> {
+        self.fitness.as_deref()
+    }
+
+    /// Preset name, `target` or path of a file, `None` for the default charset.
+    pub fn charset(&self) -> Option<&str> {
+        self.charset.as_deref()
+    }
+
+    /// How genes mutate over the charset, `None` for uniformly.
+    pub fn distribution(&self) -> Option<&str> {
+        self.distribution.as_deref()
+    }
+
+    /// Starting length of a variable-length chromosome, `None` for fixed length.
+    pub fn initial_length(&self) -> Option<usize> {
+        self.initial_length
+    }
+
+    /// Size of the population when running the genetic algorithm instead
+    /// of the weasel.
+    pub fn population(&self) -> Option<usize> {
+        self.population
+    }
+
+    pub fn crossover(&self) -> Option<&str> {
+        self.crossover.as_deref()
+    }
+
+    /// Name of the selection, optionally followed by `:param`.
+    pub fn selection(&self) -> Option<&str> {
+        self.selection.as_deref()
+    }
+
+    pub fn mu(&self) -> usize {
+        self.mu
+    }
+
+    /// `comma` or `plus`, `None` to keep the chromosome's default.
+    pub fn replacement(&self) -> Option<&str> {
+        self.replacement.as_deref()
+    }
+
+    pub fn elitism(&self) -> usize {
+        self.elitism
+    }
+
+    /// Whether genes matching the target stop mutating.
+    pub fn latching(&self) -> bool {
+        self.latching
+    }
+
+    /// How the mutation rate changes along the run, `None` keeps it fixed.
+    pub fn mr_adaptation(&self) -> Option<&str> {
+        self.mr_adaptation.as_deref()
+    }
+
+    /// Mutation operator, optionally followed by `:param`.
+    pub fn mutation(&self) -> Option<&str> {
+        self.mutation.as_deref()
+    }
+
+    pub fn max_generations(&self) -> Option<u32> {
+        self.max_generations
+    }
+
+    /// Wall-clock limit of the run.
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    pub fn target_fitness(&self) -> u32 {
+        self.target_fitness
+    }
+
+    /// Generations without improvement before giving up, 0 never does.
+    pub fn stagnation(&self) -> Option<u32> {
+        self.stagnation
+    }
+
     pub fn set_mr(&mut self, mr: f64) {
         self.mr = mr;
     }
 
-    pub fn set_ncopies(&mut self, nc: u32) {
-        self.ncopies = nc;
+    pub fn set_seed(&mut self, sd: u64) {
+        self.seed = Some(sd);
+    }
+}
+
+// -- Tests: --------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command(args: &[&str]) -> Result<Command, clap::Error> {
+        Cli::try_parse_from(["weasel-rs"].iter().chain(args)).map(Cli::into_command)
+    }
+
+    fn parse(args: &[&str]) -> Result<Arguments, clap::Error> {
+        match command(args)? {
+            Command::Run(a) => Ok(a),
+            c => panic!("not a run: {c:?}"),
+        }
+    }
+
+    #[test]
+    fn defaults() {
+        let a = Arguments::default();
+
+        assert_eq!(a.sentence(), "Me thinks it's like a weasel");
+        assert_eq!((a.mr(), a.ncopies(), a.mu()), (0.08, 500, 1));
+        assert_eq!((a.seed(), a.fitness(), a.stagnation()), (None, None, None));
     }
 
-    pub fn set_encoded(&mut self, e: bool) {
-        self.encoded = e
+    #[test]
+    fn flags() {
+        let a = parse(&["-s", "hola", "--mrate", "0.5", "--lambda", "20", "-d"]).unwrap();
+        assert_eq!((a.sentence(), a.mr(), a.ncopies()), ("hola", 0.5, 20));
+        assert!(a.encoded() && !a.latching());
+
+        let a = parse(&["-t", "tournament:3", "--mutation", "exactly:2", "-g", "9"]).unwrap();
+        assert_eq!(a.selection(), Some("tournament:3"));
+        assert_eq!(a.mutation(), Some("exactly:2"));
+        assert_eq!(a.max_generations(), Some(9));
+    }
+
+    #[test]
+    fn bad_values() {
+        let kind = |args: &[&str]| parse(args).unwrap_err().kind();
+
+        assert_eq!(kind(&["-m", "abc"]), ErrorKind::ValueValidation);
+        assert_eq!(kind(&["-m", "1.5"]), ErrorKind::ValueValidation);
+        assert_eq!(kind(&["-n", "0"]), ErrorKind::ValueValidation);
+        assert_eq!(kind(&["-s", ""]), ErrorKind::InvalidValue);
+        assert_eq!(kind(&["-f", "nope"]), ErrorKind::ValueValidation);
+        assert_eq!(kind(&["--timeout=-1"]), ErrorKind::ValueValidation);
+        assert_eq!(kind(&["--timeout", "1e20"]), ErrorKind::ValueValidation);
+        assert_eq!(kind(&["--timeout", "1e-10"]), ErrorKind::ValueValidation);
+        assert_eq!(
+            parse(&["--timeout", "1.5"]).unwrap().timeout(),
+            Some(Duration::from_millis(1500))
+        );
+        assert_eq!(kind(&["-n"]), ErrorKind::InvalidValue);
+        assert_eq!(kind(&["--nope"]), ErrorKind::UnknownArgument);
+        assert_eq!(kind(&["--format", "xml"]), ErrorKind::ValueValidation);
+        for bad in [
+            ["--mutation", "exactly:0"],
+            ["--mr-adaptation", "rechenberg:-1"],
+            ["--mr-adaptation", "self-adaptive:nan"],
+            ["--mr-adaptation", "one-fifth:0"],
+            ["--distribution", "keyboard:nan"],
+            ["--distribution", "drift:0"],
+            ["-t", "tournament:0"],
+            ["-t", "rank:3"],
+            ["-t", "truncation:nan"],
+        ] {
+            assert_eq!(kind(&bad), ErrorKind::ValueValidation, "{bad:?}");
+        }
+        assert!(
+            parse(&["-t", "rank:nan"])
+                .unwrap_err()
+                .to_string()
+                .contains("the parameter of rank must be in [1, 2], not \"nan\"")
+        );
+        assert_eq!(
+            kind(&["--format", "csv", "-d"]),
+            ErrorKind::ArgumentConflict
+        );
+        assert_eq!(kind(&["--help"]), ErrorKind::DisplayHelp);
+    }
+
+    #[test]
+    fn config() {
+        let path = std::env::temp_dir().join(format!("weasel-{}.toml", std::process::id()));
+        let toml = "sentence = \"hola\"\nmrate = 0.2\nseed = 3\nformat = \"csv\"\n";
+        std::fs::write(&path, toml).unwrap();
+        let path = path.to_str().unwrap();
+        let parse = |args: &[&str]| {
+            let args = ["weasel-rs"].iter().chain(args);
+            Cli::try_from_args(args).map(Cli::into_command)
+        };
+
+        match parse(&["run", "--config", path, "-m", "0.5", "-d"]).unwrap() {
+            Command::Run(a) => {
+                assert_eq!((a.sentence(), a.mr(), a.seed()), ("hola", 0.5, Some(3)));
+                assert!(a.encoded() && a.format().is_none());
+                assert_eq!(a.config().and_then(|p| p.to_str()), Some(path));
+            }
+            c => panic!("{c:?}"),
+        }
+        match parse(&["bench", "--config", path, "--runs", "2"]).unwrap() {
+            Command::Bench(b) => {
+                assert_eq!((b.run().seed(), b.run().format()), (Some(3), None))
+            }
+            c => panic!("{c:?}"),
+        }
+
+        let mut a = Arguments::default();
+        let bad = |toml| {
+            let c = RunConfig::from_toml(toml).unwrap();
+            Arguments::default()
+                .merge_config(&c, |_| false)
+                .unwrap_err()
+        };
+        assert_eq!(bad("mrate = 1.5"), "mrate: 1.5 is not in [0, 1]");
+        assert!(bad("selection = \"nope\"").starts_with("selection: unknown name"));
+        assert!(bad("ncopies = 0").starts_with("ncopies:"));
+        assert!(bad("encoded = true\nformat = \"csv\"").contains("together"));
+        let c = RunConfig::from_toml("mrate = 1.5").unwrap();
+        assert!(a.merge_config(&c, |id| id == "mr").is_ok());
+
+        std::fs::write(path, "mrate = 2").unwrap();
+        let e = parse(&["--config", path]).unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::ValueValidation);
+        std::fs::remove_file(path).unwrap();
+        assert!(parse(&["--config", path]).is_err());
+    }
+
+    #[test]
+    fn commands() {
+        let a = parse(&["run", "-s", "hola"]).unwrap();
+        assert_eq!(a.sentence(), "hola");
+
+        match command(&["bench", "--runs", "3", "-r", "7"]).unwrap() {
+            Command::Bench(b) => assert_eq!((b.runs(), b.run().seed()), (3, Some(7))),
+            c => panic!("{c:?}"),
+        }
+        match command(&["sweep", "--from", "0.1", "--to", "0.3", "--steps", "3"]).unwrap() {
+            Command::Sweep(s) => {
+                let rates = s.rates();
+                assert_eq!(rates.len(), 3);
+                assert!((rates[1] - 0.2).abs() < 1e-12 && rates[2] == 0.3);
+            }
+            c => panic!("{c:?}"),
+        }
+        assert!(matches!(
+            command(&["charset", "validate", "hola", "-a", "lowercase"]),
+            Ok(Command::Charset(CharsetCommand::Validate { .. }))
+        ));
+
+        // Flags of run go after it, or with no command at all.
+        let kind = |args: &[&str]| command(args).unwrap_err().kind();
+        assert_eq!(kind(&["-s", "hola", "bench"]), ErrorKind::ArgumentConflict);
+        assert_eq!(kind(&["sweep", "--to", "2"]), ErrorKind::ValueValidation);
+        assert_eq!(
+            kind(&["charset"]),
+            ErrorKind::DisplayHelpOnMissingArgumentOrSubcommand
+        );
     }
 }
@@ -13,45 +13,305 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
-use once_cell::sync::Lazy;
-use std::string::String;
-// import commonly used items from the prelude:
-//use rand::prelude::*;
+use crate::libweasel::distribution::{MutationDistribution, Uniform};
+use crate::libweasel::error::{Result, WeaselError};
+use rand::Rng;
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::ops::RangeInclusive;
+use std::path::Path;
+use std::sync::Arc;
+use unicode_normalization::UnicodeNormalization;
 
-static CHARSET: Lazy<String> = Lazy::new(|| {
-    let s = r#" "',.;:_-?abcdefghijklmnñopqrstuvwxyzABCDEFGHIJKLMNÑOPQRSTUVWXYZ0123456789"#;
+/// The charset of the original weasel program.
+const WEASEL: &str =
+    r#" "',.;:_-?abcdefghijklmnñopqrstuvwxyzABCDEFGHIJKLMNÑOPQRSTUVWXYZ0123456789"#;
 
-    format!("{}", s)
-});
+const SPANISH: &str =
+    r#" "',.;:_-?¿!¡abcdefghijklmnñopqrstuvwxyzáéíóúüABCDEFGHIJKLMNÑOPQRSTUVWXYZÁÉÍÓÚÜ0123456789"#;
 
-pub fn in_char_set(c: char) -> bool {
-    CHARSET.contains(c)
+// -- Classes: ------------------------------------------------------------
+
+/// The characters a gene can take.  Every char is stored once, in the
+/// order it was first given, and control characters are left out.
+#[derive(Clone)]
+pub struct Charset {
+    /// Indexed by `rand_char`
+    chars: Vec<char>,
+    /// The same chars, sorted for `contains`
+    sorted: Vec<char>,
+    /// What a gene turns into when it mutates
+    distribution: Arc<dyn MutationDistribution>,
 }
 
-pub fn rand_char() -> char {
-    let i = (rand::random::<u64>() as usize) % CHARSET.chars().count();
-    let c = CHARSET.chars().nth(i);
+// -- Impl. blocks: -------------------------------------------------------
 
-    c.unwrap()
+impl Default for Charset {
+    /// The charset of the original weasel program.
+    fn default() -> Self {
+        Charset::new(WEASEL.chars()).unwrap()
+    }
 }
 
+impl Charset {
+    /// Names accepted by `from_name`.
+    pub const NAMES: [&'static str; 8] = [
+        "weasel",
+        "ascii",
+        "lowercase",
+        "dna",
+        "binary",
+        "spanish",
+        "latin1",
+        "unicode",
+    ];
+
+    /// A charset made of `chars`.  It fails if there is no char left.
+    pub fn new(chars: impl IntoIterator<Item = char>) -> Result<Self> {
+        let mut seen = HashSet::new();
+        let chars: Vec<char> = chars
+            .into_iter()
+            .filter(|c| !c.is_control() && seen.insert(*c))
+            .collect();
+
+        if chars.is_empty() {
+            return Err(WeaselError::EmptyCharset);
+        }
+
+        let mut sorted = chars.clone();
+        sorted.sort_unstable();
+
+        Ok(Charset {
+            chars,
+            sorted,
+            distribution: Arc::new(Uniform),
+        })
+    }
+
+    /// All the chars of `ranges`.
+    pub fn from_ranges(ranges: &[RangeInclusive<char>]) -> Result<Self> {
+        Charset::new(ranges.iter().flat_map(|r| r.clone()))
+    }
+
+    /// Just the chars the target is made of, once NFC-normalized as the
+    /// chromosome does, so a decomposed `ñ` counts as one char.
+    pub fn from_target(target: &str) -> Result<Self> {
+        Charset::new(target.nfc())
+    }
+
+    /// The chars found in the file at `path`.  Line breaks don't count.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)
+            .map_err(|e| WeaselError::Io(format!("{}: {e}", path.display())))?;
+
+        Charset::new(text.chars())
+    }
+
+    /// One of the shipped charsets:
+    ///  - `weasel`: letters (ñ included), digits and some punctuation.
+    ///  - `ascii`: printable ASCII.
+    ///  - `lowercase`: 'a' to 'z' and the space.
+    ///  - `dna`: the four bases, `ACGT`.
+    ///  - `binary`: `01`.
+    ///  - `spanish`: `weasel` plus accented vowels, ü, ¿ and ¡.
+    ///  - `latin1`: printable ISO-8859-1.
+    ///  - `unicode`: the whole Basic Multilingual Plane but private use.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let cs = match name {
+            "weasel" => Charset::default(),
+            "ascii" => Charset::from_ranges(&[' '..='~']).ok()?,
+            "lowercase" => Charset::from_ranges(&[' '..=' ', 'a'..='z']).ok()?,
+            "dna" => Charset::new("ACGT".chars()).ok()?,
+            "binary" => Charset::new("01".chars()).ok()?,
+            "spanish" => Charset::new(SPANISH.chars()).ok()?,
+            "latin1" => Charset::from_ranges(&[' '..='~', '\u{a0}'..='ÿ']).ok()?,
+            "unicode" => Charset::from_ranges(&[' '..='\u{d7ff}', '\u{f900}'..='\u{fffd}']).ok()?,
+            _ => return None,
+        };
+
+        Some(cs)
+    }
+
+    /// Mutate genes with `d` instead of drawing uniformly from the charset.
+    pub fn with_distribution(mut self, d: Arc<dyn MutationDistribution>) -> Self {
+        self.distribution = d;
+        self
+    }
+
+    pub fn distribution_name(&self) -> &'static str {
+        self.distribution.name()
+    }
+
+    pub fn contains(&self, c: char) -> bool {
+        self.sorted.binary_search(&c).is_ok()
+    }
+
+    /// Number of chars.
+    pub fn len(&self) -> usize {
+        self.chars.len()
+    }
+
+    /// Always false, as an empty charset can't be built.
+    pub fn is_empty(&self) -> bool {
+        self.chars.is_empty()
+    }
+
+    pub fn chars(&self) -> impl Iterator<Item = char> + '_ {
+        self.chars.iter().copied()
+    }
+
+    /// Characters of `s` outside the charset, with their (char) positions.
+    pub fn invalid_chars(&self, s: &str) -> Vec<(usize, char)> {
+        s.chars()
+            .enumerate()
+            .filter(|(_, c)| !self.contains(*c))
+            .collect()
+    }
+
+    /// The chars with a code point in [lo, hi].
+    pub(crate) fn within(&self, lo: u32, hi: u32) -> &[char] {
+        let from = self.sorted.partition_point(|&c| (c as u32) < lo);
+        let to = self.sorted.partition_point(|&c| (c as u32) <= hi);
+
+        &self.sorted[from..to.max(from)]
+    }
+
+    /// A char drawn uniformly, in constant time.
+    pub fn rand_char<R: Rng + ?Sized>(&self, rng: &mut R) -> char {
+        self.chars[rng.random_range(0..self.chars.len())]
+    }
+
+    /// What `current` turns into when it mutates, according to the
+    /// distribution of the charset.
+    pub fn mutate_char<R: Rng + ?Sized>(&self, current: char, mut rng: &mut R) -> char {
+        self.distribution.mutate(current, self, &mut rng)
+    }
+}
+
+impl fmt::Debug for Charset {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Charset")
+            .field("chars", &String::from_iter(&self.chars))
+            .field("distribution", &self.distribution_name())
+            .finish()
+    }
+}
+
+impl PartialEq for Charset {
+    fn eq(&self, other: &Self) -> bool {
+        self.chars == other.chars && self.distribution_name() == other.distribution_name()
+    }
+}
+
+impl Eq for Charset {}
+
+// -- Tests: --------------------------------------------------------------
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::libweasel::rng;
 
     #[test]
     fn not_in_charset() {
-        assert!(!in_char_set('='));
-        assert!(!in_char_set('@'));
-        assert!(!in_char_set('·'));
+        let cs = Charset::default();
+
+        assert!(!cs.contains('='));
+        assert!(!cs.contains('@'));
+        assert!(!cs.contains('·'));
     }
 
     #[test]
     fn in_charset() {
-        assert!(in_char_set(' '));
-        assert!(in_char_set('a'));
-        assert!(in_char_set('A'));
-        assert!(in_char_set('1'));
-        assert!(in_char_set('_'));
+        let cs = Charset::default();
+
+        assert!(cs.contains(' '));
+        assert!(cs.contains('a'));
+        assert!(cs.contains('A'));
+        assert!(cs.contains('1'));
+        assert!(cs.contains('_'));
+        assert!(cs.contains('ñ'));
+    }
+
+    #[test]
+    fn invalid_chars_positions() {
+        let cs = Charset::default();
+
+        assert!(cs.invalid_chars("Me thinks it's like a weasel").is_empty());
+        assert_eq!(cs.invalid_chars("ñu@x=y"), vec![(2, '@'), (4, '=')]);
+    }
+
+    #[test]
+    fn rand_char_is_seeded() {
+        let cs = Charset::default();
+        let mut r1 = rng::from_seed(7);
+        let mut r2 = rng::from_seed(7);
+        let s1: String = (0..32).map(|_| cs.rand_char(&mut r1)).collect();
+        let s2: String = (0..32).map(|_| cs.rand_char(&mut r2)).collect();
+
+        assert_eq!(s1, s2);
+        assert!(s1.chars().all(|c| cs.contains(c)));
+    }
+
+    #[test]
+    fn rand_char_is_uniform() {
+        let cs = Charset::from_name("dna").unwrap();
+        let mut rng = rng::from_seed(11);
+        let mut h = [0; 4];
+        for _ in 0..40_000 {
+            let r = cs.rand_char(&mut rng);
+            h[cs.chars().position(|c| c == r).unwrap()] += 1;
+        }
+
+        assert!(h.iter().all(|&n| (9_500..10_500).contains(&n)));
+    }
+
+    #[test]
+    fn within() {
+        let cs = Charset::default();
+
+        assert_eq!(cs.within('a' as u32, 'd' as u32), &['a', 'b', 'c', 'd']);
+        assert_eq!(cs.within('ñ' as u32, 'ñ' as u32 + 100), &['ñ']);
+        assert!(cs.within('{' as u32, '~' as u32).is_empty());
+    }
+
+    #[test]
+    fn presets() {
+        for name in Charset::NAMES {
+            assert!(Charset::from_name(name).is_some());
+        }
+        assert!(Charset::from_name("nope").is_none());
+
+        assert_eq!(Charset::from_name("dna").unwrap().len(), 4);
+        assert_eq!(Charset::from_name("ascii").unwrap().len(), 95);
+        assert!(Charset::from_name("spanish").unwrap().contains('¿'));
+        assert!(Charset::from_name("unicode").unwrap().contains('λ'));
+    }
+
+    #[test]
+    fn from_target() {
+        let cs = Charset::from_target("banana\n").unwrap();
+
+        assert_eq!(cs.chars().collect::<String>(), "ban");
+        assert_eq!(Charset::from_target("\n"), Err(WeaselError::EmptyCharset));
+        assert_eq!(
+            Charset::from_target("an\u{303}o")
+                .unwrap()
+                .chars()
+                .collect::<String>(),
+            "año"
+        );
+    }
+
+    #[test]
+    fn from_file() {
+        let path = std::env::temp_dir().join("weasel-charset-test.txt");
+        fs::write(&path, "ACGT\nACGU\n").unwrap();
+        let cs = Charset::from_file(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(cs.chars().collect::<String>(), "ACGTU");
+        assert!(matches!(Charset::from_file(&path), Err(WeaselError::Io(_))));
     }
 }
@@ -13,158 +13,814 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
+use crate::libweasel::adaptation::{MrAdaptation, MrAdapter};
+use crate::libweasel::allele::Allele;
+use crate::libweasel::charset::Charset;
+use crate::libweasel::error::{Result, WeaselError};
+use crate::libweasel::fitness::{Fitness, Hamming, Levenshtein};
 use crate::libweasel::gene::{
-    Gene, GeneCreationExt, GeneExt, GeneList, MutableGene, MutableGeneExt,
+    Gene, GeneAllele, GeneCreationExt, GeneExt, GeneList, MutableGene, MutableGeneExt,
+};
+use crate::libweasel::mutation::{Bernoulli, MutationOperator};
+use crate::libweasel::result::{EvolutionResult, GenerationRecord, Snapshot};
+use crate::libweasel::rng::{self, WeaselRng};
+use crate::libweasel::selection::{Best, Replacement, Selection};
+use crate::libweasel::termination::{
+    CancelFlag, Termination, TerminationMonitor, TerminationReason,
 };
 use colored::Colorize;
+use rand::Rng;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use unicode_normalization::UnicodeNormalization;
 // use delegate::delegate;
 use signals2::*;
 use std::fmt;
 use std::ops::{Index, IndexMut};
-use std::rc::Rc;
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
 
 // pub type GeneList = Vec<Box<Gene>>;
-pub type StandardChromosome = Chromosome<Gene>;
-pub type EvolvingChromosome = Chromosome<MutableGene>;
-pub trait ChromosomeExt: GeneCreationExt + GeneExt + Clone + 'static {}
+pub type StandardChromosome<A = char> = Chromosome<Gene<A>>;
+pub type EvolvingChromosome<A = char> = Chromosome<MutableGene<A>>;
+/// The values genes of type `T` can take.
+pub type DomainOf<T> = <<T as GeneAllele>::Allele as Allele>::Domain;
+
+pub trait ChromosomeExt:
+    GeneAllele
+    + GeneCreationExt<<Self as GeneAllele>::Allele>
+    + GeneExt<<Self as GeneAllele>::Allele>
+    + Clone
+    + Send
+    + Sync
+    + 'static
+{
+}
 
 #[derive(Clone)]
 pub struct Chromosome<T: ChromosomeExt> {
     // -- Data members: -------------------------------------------------------
-    /// The signal to emit; (it, best_fit, current_chromosome)
-    pub on_evolve_iteration: Signal<(u32, u32, Rc<Self>)>,
-    /// Our target string
-    target_string: String,
+    /// The signal to emit; (it, best_fit, mr, current_chromosome)
+    pub on_evolve_iteration: Signal<(u32, u32, f64, Arc<Self>)>,
+    /// Emitted when a run starts; (best_fit, starting_chromosome)
+    pub on_start: Signal<(u32, Arc<Self>)>,
+    /// Emitted only when the best fitness drops; (it, old_best_fit, best_fit, current_chromosome)
+    pub on_improvement: Signal<(u32, u32, u32, Arc<Self>)>,
+    /// Emitted for every child scored; (it, child_fit)
+    pub on_child_evaluated: Signal<(u32, u32)>,
+    /// Emitted after every generation that does not improve the best
+    /// fitness; (it, generations_without_improvement)
+    pub on_stagnation: Signal<(u32, u32)>,
+    /// Emitted when `evolve`, or an `evolution`, ends; (result)
+    pub on_finish: Signal<(EvolutionResult<T::Allele>,)>,
+    /// Our target, one allele per gene
+    target: Vec<T::Allele>,
+    /// The values genes can take, a charset for chars
+    domain: Arc<DomainOf<T>>,
     /// Number of copies in each evolution
     ncopies: u32,
     /// The gene list of this chromosome
     gene_list: GeneList<T>,
     /// Mutation rate
     mr: f64,
+    /// How `mr` changes along the run
+    mr_adaptation: MrAdaptation,
+    /// Which genes of a child mutate
+    mutation: Arc<dyn MutationOperator>,
+    /// Whether `evolve` keeps a record of every generation
+    history: bool,
+    /// The run going on, if any
+    run: Option<Run<T>>,
+    /// How close the genes are to the target
+    fitness_fn: Arc<dyn Fitness<T::Allele>>,
+    /// How the next parents are picked among the candidates
+    selection: Arc<dyn Selection>,
+    /// Number of parents (μ), the children per generation (λ) being `ncopies`
+    mu: usize,
+    /// Whether parents compete with their children, (μ+λ), or not, (μ,λ)
+    replacement: Replacement,
+    /// Number of best parents that always survive
+    elitism: usize,
+    /// When `evolve` stops
+    termination: Termination,
+    /// Whether mutation may change the number of genes
+    variable_length: bool,
+    /// Whether genes matching the target are locked, so they never mutate again
+    latching: bool,
+    /// Mutation rate of every gene, by position, overriding `mr`
+    gene_mrs: Vec<f64>,
+    /// Number of random genes to start with, the target length if `None`
+    initial_length: Option<usize>,
+    /// Seed of `rng`, so the run can be replayed
+    seed: u64,
+    /// Source of every random decision taken by this chromosome
+    rng: WeaselRng,
+    /// Raised by `request_cancel`, shared by the copies of the chromosome
+    /// and lowered when a run starts
+    cancel: CancelFlag,
+}
+
+/// The state of a run between two generations.
+#[derive(Clone)]
+struct Run<T: ChromosomeExt> {
+    /// Generations evolved so far
+    it: u32,
+    evaluations: u64,
+    history: Option<Vec<GenerationRecord>>,
+    monitor: TerminationMonitor,
+    adapter: MrAdapter,
+    /// The μ parents, the fittest first, with their fitness and mutation rates
+    parents: Vec<GeneList<T>>,
+    pfit: Vec<u32>,
+    pmr: Vec<f64>,
+}
+
+/// Iterator over the generations of a run, see `evolution`.
+pub struct Evolution<'a, A: Allele> {
+    chromosome: &'a mut EvolvingChromosome<A>,
+    done: bool,
 }
 
 // -- Impl. blocks: -------------------------------------------------------
-impl ChromosomeExt for Gene {}
-impl ChromosomeExt for MutableGene {}
+impl<A: Allele> ChromosomeExt for Gene<A> {}
+impl<A: Allele> ChromosomeExt for MutableGene<A> {}
+
+impl<A: Allele> Iterator for Evolution<'_, A> {
+    type Item = Snapshot<A>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let s = self.chromosome.step();
+        if let Some(reason) = s.reason {
+            self.done = true;
+            self.chromosome.finish(reason);
+        }
+        Some(s)
+    }
+}
 
 impl<T: ChromosomeExt> fmt::Display for Chromosome<T> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let mut gstr = String::new();
         // Escribimos en el formateador 'f' la representación que queremos
-        self.gene_list.iter().for_each(|e| {
-            let c = <T as GeneExt>::get(e);
-            gstr.push(c);
-        });
-        write!(f, "{}", gstr)
+        write!(f, "{}", Self::gene_list_as_string(&self.gene_list))
     }
 }
 
-impl Chromosome<MutableGene> {
+impl<A: Allele> Chromosome<MutableGene<A>> {
     pub fn with_mr(mut self, mr: f64) -> Self {
         self.mr = mr;
         self
     }
 
-    fn mutate_genes(&self, v: &mut GeneList<MutableGene>) {
-        for i in 0..self.size() {
-            //let c = Box::new(self[i].clone());
-            let c = &self[i];
+    /// Let mutation insert, delete and duplicate genes, starting from
+    /// `initial_len` random genes (0 starts from an empty string).  The
+    /// fitness becomes `Levenshtein`, call `with_fitness` afterwards to
+    /// use another one.
+    pub fn with_variable_length(mut self, initial_len: usize) -> Self {
+        self.variable_length = true;
+        self.initial_length = Some(initial_len);
+        self.fitness_fn = Arc::new(Levenshtein);
+        self.create_random_genes();
+        self
+    }
+
+    /// Latching weasel: once a gene matches the target it is locked and
+    /// never mutates again.  Non-latching, the default, lets right genes
+    /// go wrong again.  Only fixed length chromosomes latch.
+    pub fn with_latching(mut self, latching: bool) -> Self {
+        self.latching = latching;
+        self
+    }
+
+    /// Gene `i` mutates with probability `mrs[i]` instead of `mr`, genes
+    /// past the end of `mrs` use `mr`.  Only fixed length chromosomes
+    /// take rates by position.
+    pub fn with_gene_mrs(mut self, mrs: Vec<f64>) -> Self {
+        self.gene_mrs = mrs;
+        self
+    }
+
+    /// Change `mr` along the run instead of keeping the one given to
+    /// `with_mr`, which becomes the starting rate.
+    pub fn with_mr_adaptation(mut self, a: MrAdaptation) -> Self {
+        self.mr_adaptation = a;
+        self
+    }
+
+    /// Pick the genes that mutate with `m` instead of `Bernoulli`.  Genes
+    /// with their own rate still mutate on their own, and only fixed
+    /// length chromosomes use operators.
+    pub fn with_mutation(mut self, m: Arc<dyn MutationOperator>) -> Self {
+        self.mutation = m;
+        self
+    }
+
+    /// Make `evolve` return the best fitness and mutation rate of every
+    /// generation.
+    pub fn with_history(mut self, history: bool) -> Self {
+        self.history = history;
+        self
+    }
+
+    /// A mutated copy of `parent`.
+    pub(crate) fn mutate_genes<R: Rng + ?Sized>(
+        &self,
+        parent: &GeneList<MutableGene<A>>,
+        rng: &mut R,
+    ) -> GeneList<MutableGene<A>> {
+        self.mutate_genes_with(parent, self.mr, rng)
+    }
+
+    /// A copy of `parent` mutated with rate `mr`.
+    fn mutate_genes_with<R: Rng + ?Sized>(
+        &self,
+        parent: &GeneList<MutableGene<A>>,
+        mr: f64,
+        rng: &mut R,
+    ) -> GeneList<MutableGene<A>> {
+        if self.variable_length {
+            return self.mutate_genes_variable(parent, mr, rng);
+        }
+
+        // Locked genes never mutate and genes with their own rate mutate
+        // on their own, the operator picks among the rest.
+        let mut v = parent.clone();
+        let mut candidates = Vec::with_capacity(v.len());
+        for (i, g) in v.iter_mut().enumerate() {
+            self.prepare_gene(i, g);
+            if g.mr().is_some() {
+                g.mutate_data(mr, &self.domain, rng);
+                self.latch(i, g);
+            } else if !g.is_locked() {
+                candidates.push(i);
+            }
+        }
+
+        let mut rng = rng;
+        for k in self.mutation.positions(candidates.len(), mr, &mut rng) {
+            let (i, g) = (candidates[k], &mut v[candidates[k]]);
+            g.set(g.get().mutate(&self.domain, rng));
+            self.latch(i, g);
+        }
+
+        v
+    }
+
+    /// Give gene `i` its own mutation rate, if it has none yet.  Random
+    /// genes may already be right, so they are latched too.
+    fn prepare_gene(&self, i: usize, g: &mut MutableGene<A>) {
+        if g.mr().is_none() {
+            g.set_mr(self.gene_mrs.get(i).copied());
+        }
+        self.latch(i, g);
+    }
+
+    /// Lock gene `i` when latching and it matches the target.
+    fn latch(&self, i: usize, g: &mut MutableGene<A>) {
+        if self.latching && self.target.get(i) == Some(&g.get()) {
+            g.lock();
+        }
+    }
+
+    /// Every gene mutates with probability `mr`, the kind of mutation
+    /// being a substitution, an insertion before it, a deletion or a
+    /// duplication.
+    fn mutate_genes_variable<R: Rng + ?Sized>(
+        &self,
+        parent: &GeneList<MutableGene<A>>,
+        mr: f64,
+        rng: &mut R,
+    ) -> GeneList<MutableGene<A>> {
+        let mut v = GeneList::with_capacity(parent.len() + 1);
+
+        for g in parent.iter() {
+            if g.is_locked() {
+                v.push(g.clone());
+                continue;
+            }
 
-            v[i].set(c.get());
-            v[i].mutate_data(self.mr());
+            let p: f64 = rng.random();
+            if p >= g.mr().unwrap_or(mr) {
+                v.push(g.clone());
+                continue;
+            }
+
+            match rng.random_range(0..4) {
+                // Substitution
+                0 => {
+                    let mut g = g.clone();
+                    g.set(g.get().mutate(&self.domain, rng));
+                    v.push(g);
+                }
+                // Insertion
+                1 => {
+                    v.push(Box::new(MutableGene::new_from_random(&*self.domain, rng)));
+                    v.push(g.clone());
+                }
+                // Deletion
+                2 => (),
+                // Duplication
+                _ => {
+                    v.push(g.clone());
+                    v.push(g.clone());
+                }
+            }
+        }
+
+        // Genes can also be appended, otherwise an empty chromosome would never grow.
+        let p: f64 = rng.random();
+        if p < mr {
+            v.push(Box::new(MutableGene::new_from_random(&*self.domain, rng)));
         }
+
+        v
+    }
+
+    /// Pick the next parents with `s` instead of keeping the `Best` ones.
+    pub fn with_selection(mut self, s: Arc<dyn Selection>) -> Self {
+        self.selection = s;
+        self
     }
 
+    /// Number of parents (μ) of every generation, 1 by default.
+    pub fn with_mu(mut self, mu: usize) -> Self {
+        self.mu = mu.max(1);
+        self
+    }
+
+    /// (μ,λ) or (μ+λ) evolution strategy, (1+λ) by default.
+    pub fn with_replacement(mut self, replacement: Replacement) -> Self {
+        self.replacement = replacement;
+        self
+    }
+
+    /// The `elitism` best parents are never replaced by worse ones,
+    /// whatever the replacement and selection are.
+    pub fn with_elitism(mut self, elitism: usize) -> Self {
+        self.elitism = elitism;
+        self
+    }
+
+    /// The current mutation rate, which changes along the run if adapted.
     pub fn mr(&self) -> f64 {
         self.mr
     }
 
-    pub fn evolve(&mut self) {
-        let mut it: u32 = 0;
-        let mut glc: GeneList<MutableGene> = vec![]; // Gene list copy
-        let mut bgl: GeneList<MutableGene> = vec![]; // Best Gene list copy
+    pub fn mr_adaptation(&self) -> MrAdaptation {
+        self.mr_adaptation
+    }
 
-        self.gene_list.iter().for_each(|e| {
-            let mg1 = Box::new(MutableGene::new(e.get()));
-            let mg2 = Box::new(MutableGene::new(e.get()));
-            glc.push(mg1);
-            bgl.push(mg2);
-        });
+    pub fn mutation_name(&self) -> &'static str {
+        self.mutation.name()
+    }
 
-        // println!("glc: {:?}", glc);
+    pub fn selection_name(&self) -> &'static str {
+        self.selection.name()
+    }
 
-        // Best fit til now.
-        let mut bf: u32 = self.fitness(&glc);
+    pub fn mu(&self) -> usize {
+        self.mu
+    }
+
+    pub fn termination(&self) -> &Termination {
+        &self.termination
+    }
+
+    pub fn replacement(&self) -> Replacement {
+        self.replacement
+    }
+
+    pub fn elitism(&self) -> usize {
+        self.elitism
+    }
+
+    pub fn variable_length(&self) -> bool {
+        self.variable_length
+    }
+
+    pub fn latching(&self) -> bool {
+        self.latching
+    }
+
+    /// Evolve until one of the `Termination` criteria is met.  The genes
+    /// are left on the best gene list found.  Every call starts a new run
+    /// from the current genes.
+    pub fn evolve(&mut self) -> EvolutionResult<A> {
+        self.run = None;
 
         loop {
-            it += 1;
-            for _ in 0..self.ncopies() {
-                self.mutate_genes(&mut glc);
-                let f = self.fitness(&glc);
-
-                // println!("Loop: {it} - f: {f} - bf: {bf} - {:#?}", bgl);
-                // if it % 100 == 0 {
-                //     println!("Loop: {it} - f: {f} - bf: {bf}: {}", self.get_genes());
-                // }
-
-                if f < bf {
-                    bf = f;
-
-                    for i in 0..self.size() {
-                        bgl[i].set(glc[i].get());
-                    }
-
-                    // println!(
-                    //     "it: {it} - bf: {bf} - bgl: {:?}",
-                    //     Chromosome::gene_list_as_string(&bgl)
-                    // );
-
-                    if bf == 0 {
-                        // bestfit == 0 means the chromosome is equal to target-string.
-                        // println!("Found: {}", bf);
-                        break;
-                    }
-                }
+            if let Some(reason) = self.step().reason {
+                return self.finish(reason);
             }
+        }
+    }
 
-            self.gene_list.iter_mut().enumerate().for_each(|(i, g)| {
-                g.set(bgl[i].get());
-            });
+    /// Evolve exactly one generation, starting a run from the current genes
+    /// if there is none going on.  The snapshot tells whether one of the
+    /// `Termination` criteria is met, but the run can be stepped further.
+    pub fn step(&mut self) -> Snapshot<A> {
+        let mut run = match self.run.take() {
+            Some(run) => run,
+            None => self.start_run(),
+        };
+        run.it += 1;
+        let old_bf = run.pfit[0];
+
+        let (children, cfit, cmr) = self.create_children(
+            run.it,
+            &run.parents,
+            &run.pmr,
+            &run.adapter,
+            &mut run.evaluations,
+        );
+        let successes = cfit.iter().filter(|&&f| f < run.pfit[0]).count() as u32;
+        let trials = cfit.len() as u32;
+        let worst_child = cfit.iter().max().copied();
+        let mean_child = cfit.iter().map(|&f| f as f64).sum::<f64>() / trials.max(1) as f64;
+
+        // The next parents are chosen among the children, and among
+        // the old parents too when using plus replacement.  The elite
+        // of the old parents only leaves when μ better ones are found.
+        let elite = self.elitism.min(self.mu);
+        let (pool, pool_fit, pool_mr) = match self.replacement {
+            Replacement::Comma => (children, cfit, cmr),
+            Replacement::Plus => {
+                let mut pool = run.parents.clone();
+                let mut pool_fit = run.pfit.clone();
+                let mut pool_mr = run.pmr.clone();
+                pool.extend(children);
+                pool_fit.extend(cfit);
+                pool_mr.extend(cmr);
+                (pool, pool_fit, pool_mr)
+            }
+        };
 
-            // Emit the signal
-            let self_rc = Rc::new(self.clone());
-            self.on_evolve_iteration.emit(it, bf, self_rc.clone());
+        // In plus replacement the elite are in the pool too: they are left
+        // out of the selection, so none of them can come back twice.
+        let from = match self.replacement {
+            Replacement::Comma => 0,
+            Replacement::Plus => elite,
+        };
+        if pool.len() > from {
+            let chosen = self
+                .selection
+                .survivors(&pool_fit[from..], self.mu, &mut self.rng);
 
-            //check if we've got the target string: bf (best fit) == 0.
-            if bf == 0 {
-                break;
+            run.parents.truncate(elite);
+            run.pfit.truncate(elite);
+            run.pmr.truncate(elite);
+            for i in chosen.into_iter().map(|i| i + from) {
+                run.parents.push(pool[i].clone());
+                run.pfit.push(pool_fit[i]);
+                run.pmr.push(pool_mr[i]);
             }
+            Self::sort_by_fitness(&mut run.parents, &mut run.pfit, &mut run.pmr);
+            run.parents.truncate(self.mu);
+            run.pfit.truncate(self.mu);
+            run.pmr.truncate(self.mu);
         }
+
+        // Self-adaptive rates travel with the parents, the others are
+        // shared by everyone.
+        if matches!(self.mr_adaptation, MrAdaptation::SelfAdaptive { .. }) {
+            self.mr = run.pmr[0];
+        } else {
+            self.mr = run.adapter.adapt(self.mr, successes, trials);
+            run.pmr.fill(self.mr);
+        }
+
+        let it = run.it;
+        let bf = run.pfit[0];
+        self.gene_list = run.parents[0].clone();
+
+        // Emit the signals, the run is left out of the copy.
+        let self_arc = Arc::new(self.clone());
+        self.on_evolve_iteration
+            .emit(it, bf, self.mr, self_arc.clone());
+        if bf < old_bf {
+            self.on_improvement.emit(it, old_bf, bf, self_arc);
+        }
+
+        if let Some(h) = run.history.as_mut() {
+            h.push(GenerationRecord {
+                generation: it,
+                best_fitness: bf,
+                mr: self.mr,
+            });
+        }
+        let reason = run.monitor.check(it, bf);
+        if run.monitor.stagnant() > 0 {
+            self.on_stagnation.emit(it, run.monitor.stagnant());
+        }
+        let evaluations = run.evaluations;
+        let elapsed = run.monitor.elapsed();
+        self.run = Some(run);
+
+        Snapshot {
+            generation: it,
+            best_fitness: bf,
+            mean_fitness: if trials > 0 { mean_child } else { bf as f64 },
+            worst_fitness: worst_child.unwrap_or(bf),
+            mr: self.mr,
+            evaluations,
+            elapsed,
+            genes: self.get_alleles(),
+            reason,
+        }
+    }
+
+    /// The generations of a run, one `step` at a time.  It ends after the
+    /// generation meeting one of the `Termination` criteria, e.g.
+    /// `c.evolution().take(10)` evolves at most 10 generations.
+    pub fn evolution(&mut self) -> Evolution<'_, A> {
+        Evolution {
+            chromosome: self,
+            done: false,
+        }
+    }
+
+    /// Stop the run at the end of the current generation, with
+    /// `TerminationReason::Cancelled`.  Meant for signal slots, which get
+    /// a copy of the chromosome sharing the request.  Requests made between
+    /// runs are dropped when the next one starts; a flag given with
+    /// `Termination::with_cancel_flag` is never lowered.
+    pub fn request_cancel(&self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+
+    /// The flag raised by `request_cancel`, to cancel the run from
+    /// somewhere else, e.g. another thread.
+    pub fn cancel_flag(&self) -> CancelFlag {
+        self.cancel.clone()
+    }
+
+    /// The result of the current run, stopped for `reason`, which is also
+    /// emitted through `on_finish`.
+    fn finish(&self, reason: TerminationReason) -> EvolutionResult<A> {
+        let result = self.result(reason);
+        self.on_finish.emit(result.clone());
+
+        result
+    }
+
+    /// The result of the current run, stopped for `reason`.
+    fn result(&self, reason: TerminationReason) -> EvolutionResult<A> {
+        let run = self.run.as_ref();
+
+        EvolutionResult {
+            genes: self.get_alleles(),
+            genes_string: self.get_genes(),
+            best_fitness: run.map_or(u32::MAX, |r| r.pfit[0]),
+            generations: run.map_or(0, |r| r.it),
+            evaluations: run.map_or(0, |r| r.evaluations),
+            elapsed: run.map_or(Duration::ZERO, |r| r.monitor.elapsed()),
+            reason,
+            history: run.and_then(|r| r.history.clone()),
+        }
+    }
+
+    /// A new run whose first parent is the current gene list.
+    fn start_run(&mut self) -> Run<MutableGene<A>> {
+        // The μ parents, the fittest first, with their mutation rates.
+        let mut parents: Vec<GeneList<MutableGene<A>>> = vec![self.gene_list.clone()];
+        while parents.len() < self.mu {
+            parents.push(self.random_gene_list());
+        }
+        let mut pfit: Vec<u32> = parents.iter().map(|p| self.fitness(p)).collect();
+        let mut pmr: Vec<f64> = vec![self.mr; parents.len()];
+        Self::sort_by_fitness(&mut parents, &mut pfit, &mut pmr);
+        self.gene_list = parents[0].clone();
+        self.cancel.store(false, Ordering::Relaxed);
+        self.on_start.emit(pfit[0], Arc::new(self.clone()));
+
+        Run {
+            it: 0,
+            evaluations: parents.len() as u64,
+            history: self.history.then(Vec::new),
+            monitor: self
+                .termination
+                .monitor()
+                .with_best(pfit[0])
+                .with_requests(self.cancel.clone()),
+            adapter: self
+                .mr_adaptation
+                .adapter(1.0 / self.target.len().max(1) as f64),
+            parents,
+            pfit,
+            pmr,
+        }
+    }
+
+    /// The λ children of a generation, their fitness and mutation rates.
+    /// Every child gets its own generator seeded from `rng`, so the
+    /// children are the same whether they are created one after another
+    /// or in parallel.  All of them are scored either way, even past a
+    /// perfect one: they are added to `evaluations`, and emitted through
+    /// `on_child_evaluated` as generation `it`.
+    fn create_children(
+        &mut self,
+        it: u32,
+        parents: &[GeneList<MutableGene<A>>],
+        pmr: &[f64],
+        adapter: &MrAdapter,
+        evaluations: &mut u64,
+    ) -> (Vec<GeneList<MutableGene<A>>>, Vec<u32>, Vec<f64>) {
+        let seeds: Vec<u64> = (0..self.ncopies()).map(|_| self.rng.random()).collect();
+
+        let create_child = |seed: &u64| {
+            let mut rng = rng::from_seed(*seed);
+            let p = match parents.len() {
+                1 => 0,
+                n => rng.random_range(0..n),
+            };
+            let mr = adapter.child_mr(pmr[p], &mut rng);
+            let glc = self.mutate_genes_with(&parents[p], mr, &mut rng); // Gene list copy
+            let f = self.fitness(&glc);
+
+            (glc, f, mr)
+        };
+
+        #[cfg(feature = "parallel")]
+        let mut children: Vec<(GeneList<MutableGene<A>>, u32, f64)> =
+            seeds.par_iter().map(create_child).collect();
+
+        #[cfg(not(feature = "parallel"))]
+        let mut children: Vec<(GeneList<MutableGene<A>>, u32, f64)> =
+            seeds.iter().map(create_child).collect();
+
+        *evaluations += children.len() as u64;
+        for (_, f, _) in children.iter() {
+            self.on_child_evaluated.emit(it, *f);
+        }
+
+        // Nobody looks past the first perfect child.
+        if let Some(i) = children.iter().position(|(_, f, _)| *f == 0) {
+            children.truncate(i + 1);
+        }
+
+        let mut glcs = Vec::with_capacity(children.len());
+        let mut fits = Vec::with_capacity(children.len());
+        let mut mrs = Vec::with_capacity(children.len());
+        for (glc, f, mr) in children {
+            glcs.push(glc);
+            fits.push(f);
+            mrs.push(mr);
+        }
+
+        (glcs, fits, mrs)
+    }
+
+    /// Sort gene lists, with their mutation rates, by fitness, keeping the
+    /// order of ties.
+    fn sort_by_fitness(v: &mut Vec<GeneList<MutableGene<A>>>, f: &mut Vec<u32>, m: &mut Vec<f64>) {
+        let mut triples: Vec<_> = v.drain(..).zip(f.drain(..)).zip(m.drain(..)).collect();
+        triples.sort_by_key(|((_, fit), _)| *fit);
+        for ((glc, fit), mr) in triples {
+            v.push(glc);
+            f.push(fit);
+            m.push(mr);
+        }
+    }
+}
+
+impl<T: ChromosomeExt<Allele = char>> Chromosome<T> {
+    /// A chromosome evolving towards `tstr` with the default charset.  It
+    /// fails if `tstr` has characters outside the charset, as they could
+    /// never be reached.
+    ///
+    /// Genes are chars, so `tstr` is NFC normalized first: a decomposed
+    /// 'ñ' ('n' followed by a combining tilde) is a single gene, just like
+    /// the precomposed one.
+    pub fn new(tstr: String, ncopies: u32) -> Result<Self> {
+        Self::new_with_charset(tstr, ncopies, Charset::default())
+    }
+
+    /// Like `new`, but genes take their chars from `charset`.
+    pub fn new_with_charset(tstr: String, ncopies: u32, charset: Charset) -> Result<Self> {
+        let target: Vec<char> = tstr.nfc().collect();
+        let invalid = charset.invalid_chars(&String::from_iter(&target));
+        if !invalid.is_empty() {
+            return Err(WeaselError::InvalidTarget(invalid));
+        }
+
+        Ok(Self::build(target, ncopies, charset))
+    }
+
+    pub fn charset(&self) -> &Charset {
+        &self.domain
     }
 }
 
 impl<T: ChromosomeExt> Chromosome<T> {
     // -- Methods: ------------------------------------------------------------
-    pub fn new(tstr: String, ncopies: u32) -> Self {
-        let on_evolve_iteration = Signal::new();
+    /// A chromosome evolving towards `target`, whose genes take their
+    /// values from `domain`.  It fails if some allele of the target is
+    /// outside the domain.
+    pub fn from_alleles(target: Vec<T::Allele>, ncopies: u32, domain: DomainOf<T>) -> Result<Self> {
+        let invalid: Vec<usize> = target
+            .iter()
+            .enumerate()
+            .filter(|(_, a)| !a.in_domain(&domain))
+            .map(|(i, _)| i)
+            .collect();
+        if !invalid.is_empty() {
+            return Err(WeaselError::OutOfDomain(invalid));
+        }
+
+        Ok(Self::build(target, ncopies, domain))
+    }
+
+    fn build(target: Vec<T::Allele>, ncopies: u32, domain: DomainOf<T>) -> Self {
+        let seed = rng::random_seed();
         let mut c = Chromosome {
-            on_evolve_iteration,
-            target_string: tstr,
+            on_evolve_iteration: Signal::new(),
+            on_start: Signal::new(),
+            on_improvement: Signal::new(),
+            on_child_evaluated: Signal::new(),
+            on_stagnation: Signal::new(),
+            on_finish: Signal::new(),
+            target,
+            domain: Arc::new(domain),
             ncopies,
             gene_list: vec![],
             mr: 0.0,
+            mr_adaptation: MrAdaptation::Fixed,
+            mutation: Arc::new(Bernoulli),
+            history: false,
+            run: None,
+            fitness_fn: Arc::new(Hamming),
+            selection: Arc::new(Best),
+            mu: 1,
+            replacement: Replacement::Plus,
+            elitism: 0,
+            termination: Termination::default(),
+            variable_length: false,
+            latching: false,
+            gene_mrs: vec![],
+            initial_length: None,
+            seed,
+            rng: rng::from_seed(seed),
+            cancel: CancelFlag::default(),
         };
         c.create_random_genes();
 
         c
     }
 
+    /// Reseed the chromosome.  The random genes are created again from the
+    /// new seed, so two chromosomes built with the same target and seed
+    /// evolve through exactly the same generations.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self.rng = rng::from_seed(seed);
+        self.create_random_genes();
+        self
+    }
+
+    /// Score the genes with `f` instead of the default `Hamming` distance.
+    pub fn with_fitness(mut self, f: Arc<dyn Fitness<T::Allele>>) -> Self {
+        self.fitness_fn = f;
+        self
+    }
+
+    /// Stop evolving on `t` instead of the default `Termination`.
+    pub fn with_termination(mut self, t: Termination) -> Self {
+        self.termination = t;
+        self
+    }
+
     pub fn ncopies(&self) -> u32 {
         self.ncopies
     }
 
+    pub fn fitness_name(&self) -> &'static str {
+        self.fitness_fn.name()
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// The target as printed, e.g. the string for char genes.
     pub fn target(&self) -> String {
-        self.target_string.clone()
+        Self::alleles_as_string(&self.target)
+    }
+
+    pub fn target_alleles(&self) -> &[T::Allele] {
+        &self.target
+    }
+
+    pub fn domain(&self) -> &DomainOf<T> {
+        &self.domain
+    }
+
+    /// Number of genes of the target.
+    pub fn target_len(&self) -> usize {
+        self.target.len()
     }
 
     #[allow(unused)]
@@ -175,56 +831,82 @@ impl<T: ChromosomeExt> Chromosome<T> {
         // }
 
         self.gene_list = self
-            .target_string
-            .chars()
-            .map(|c| Box::new(T::new(c)))
+            .target
+            .iter()
+            .map(|a| Box::new(T::new(a.clone())))
             .collect();
     }
 
-    #[allow(unused)]
-    fn gene_list_as_string(gene_list: &GeneList<T>) -> String {
-        let mut gstr = String::new();
+    /// The symbols of `alleles`, joined by their separator.
+    fn alleles_as_string(alleles: &[T::Allele]) -> String {
+        let symbols: Vec<String> = alleles.iter().map(|a| a.symbol()).collect();
 
-        gene_list.iter().for_each(|e| {
-            let c = <T as GeneExt>::get(e);
-            gstr.push(c);
-        });
+        symbols.join(<T::Allele as Allele>::SEPARATOR)
+    }
 
-        gstr
+    fn gene_list_as_string(gene_list: &GeneList<T>) -> String {
+        Self::alleles_as_string(&Self::gene_list_alleles(gene_list))
+    }
+
+    fn gene_list_alleles(gene_list: &GeneList<T>) -> Vec<T::Allele> {
+        gene_list.iter().map(|g| g.get()).collect()
     }
 
     pub fn get_genes(&self) -> String {
         Self::gene_list_as_string(&self.gene_list)
     }
 
-    /// Mark wrong genes with a different color.
+    /// The alleles of the genes, in order.
+    pub fn get_alleles(&self) -> Vec<T::Allele> {
+        Self::gene_list_alleles(&self.gene_list)
+    }
+
+    /// Mark wrong genes with a different color.  Gene `i` is compared with
+    /// the `i`-th allele of the target.
     pub fn get_genes_colored(&self) -> String {
-        let gs = Self::gene_list_as_string(&self.gene_list);
-        let ts = self.target();
-        let mut coloredstr = "".to_owned();
-        let mut charstr: String = "".into();
-
-        // 1. Get the character iterators for both strings.
-        let chars_gs = gs.chars();
-        let chars_ts = ts.chars();
-
-        chars_gs.zip(chars_ts).for_each(|(g, t)| {
-            charstr = format!("{}", g);
-            if g != t {
-                // Char in gene sequence is different from the one in target string
-                charstr = format!("{}", charstr.black().on_white());
+        let mut symbols: Vec<String> = vec![];
+        let mut alleles_ts = self.target.iter();
+
+        // Variable length chromosomes may have more genes than the target has alleles.
+        self.gene_list.iter().for_each(|e| {
+            let g = e.get();
+            let mut symbol = g.symbol();
+            if alleles_ts.next() != Some(&g) {
+                // Allele in gene sequence is different from the one in target
+                symbol = format!("{}", symbol.black().on_white());
             }
-            coloredstr += &charstr;
+            symbols.push(symbol);
         });
 
-        coloredstr
+        symbols.join(<T::Allele as Allele>::SEPARATOR)
+    }
+
+    /// The genes as plain ASCII, for other programs to read: `\` is
+    /// doubled and chars outside printable ASCII are written `\u{..}`.
+    pub fn get_genes_encoded(&self) -> String {
+        encode(&self.get_genes())
     }
 
     fn create_random_genes(&mut self) {
         self.free_gene_list();
-        for _ in 0..self.target_string.len() {
-            self.gene_list.push(Box::new(T::new_from_random()));
-        }
+        self.gene_list = self.random_gene_list();
+    }
+
+    /// A new list of random genes, as many as the chromosome starts with.
+    pub(crate) fn random_gene_list(&mut self) -> GeneList<T> {
+        let len = self.initial_length.unwrap_or(self.target.len());
+
+        (0..len)
+            .map(|_| Box::new(T::new_from_random(&self.domain, &mut self.rng)))
+            .collect()
+    }
+
+    pub(crate) fn set_gene_list(&mut self, gene_list: GeneList<T>) {
+        self.gene_list = gene_list;
+    }
+
+    pub(crate) fn rng_mut(&mut self) -> &mut WeaselRng {
+        &mut self.rng
     }
 
     fn free_gene_list(&mut self) {
@@ -236,17 +918,9 @@ impl<T: ChromosomeExt> Chromosome<T> {
     }
 
     pub fn fitness(&self, v: &GeneList<T>) -> u32 {
-        let mut d: u32 = 0;
-        let mut i = 0;
+        let genes = Self::gene_list_alleles(v);
 
-        for c in self.target_string.chars() {
-            if c != (&*v[i]).get() {
-                d += 1;
-            }
-            i += 1;
-        }
-
-        d
+        self.fitness_fn.fitness(&self.target, &genes)
     }
 }
 
@@ -254,16 +928,28 @@ impl<T: ChromosomeExt> Index<usize> for Chromosome<T> {
     type Output = T;
 
     fn index(&self, idx: usize) -> &Self::Output {
-        &*self.gene_list[idx]
+        &self.gene_list[idx]
     }
 }
 
 impl<T: ChromosomeExt> IndexMut<usize> for Chromosome<T> {
     fn index_mut(&mut self, idx: usize) -> &mut Self::Output {
-        &mut *self.gene_list[idx]
+        &mut self.gene_list[idx]
     }
 }
 
+/// `s` with `\` doubled and chars outside printable ASCII escaped as
+/// `\u{..}`, in hex, like Rust string literals.
+fn encode(s: &str) -> String {
+    s.chars()
+        .map(|c| match c {
+            '\\' => "\\\\".to_string(),
+            ' '..='~' => c.to_string(),
+            _ => format!("\\u{{{:x}}}", c as u32),
+        })
+        .collect()
+}
+
 // use std::ops::{Deref, DerefMut};
 //
 // impl Deref for Chromosome {
@@ -286,15 +972,15 @@ mod tests {
 
     #[test]
     fn test_get_genes() {
-        let c = StandardChromosome::new("hola".into(), 4);
+        let c = StandardChromosome::new("hola".into(), 4).unwrap();
         let gstr = c.get_genes();
 
-        assert_eq!(gstr.len(), "hola".len());
+        assert_eq!(gstr.chars().count(), "hola".len());
     }
 
     #[test]
     fn test_evolvingchromosome1() {
-        let mut c = EvolvingChromosome::new("hola".into(), 4);
+        let mut c = EvolvingChromosome::new("hola".into(), 4).unwrap();
         c.create_genes_from_target();
         let gc0 = (c[0]).get();
         let gc1 = (c[1]).get();
@@ -305,7 +991,7 @@ mod tests {
 
     #[test]
     fn test_evolvingchromosome2() {
-        let mut c = EvolvingChromosome::new("hola".into(), 4);
+        let mut c = EvolvingChromosome::new("hola".into(), 4).unwrap();
         c.create_genes_from_target();
         c[0] = MutableGene::new('l');
         let gc0 = c[0].get();
@@ -315,7 +1001,7 @@ mod tests {
 
     #[test]
     fn test_index1() {
-        let mut c = StandardChromosome::new("hola".into(), 4);
+        let mut c = StandardChromosome::new("hola".into(), 4).unwrap();
         c.create_genes_from_target();
         let gc0: char = (&c[0]).into();
         let gc1 = c[1].get();
@@ -326,49 +1012,743 @@ mod tests {
 
     #[test]
     fn test_fitness1() {
-        let c = StandardChromosome::new("hola".into(), 4);
+        let c = StandardChromosome::new("hola".into(), 4).unwrap();
         let g1 = Box::new(Gene::new('h'));
         let g2 = Box::new(Gene::new('a'));
         let g3 = Box::new(Gene::new('l'));
         let g4 = Box::new(Gene::new('a'));
-        let mut v = vec![];
-        v.push(g1);
-        v.push(g2);
-        v.push(g3);
-        v.push(g4);
+        let v = vec![g1, g2, g3, g4];
 
         assert_eq!(c.fitness(&v), 1);
     }
 
     #[test]
     fn test_fitness2() {
-        let c = StandardChromosome::new("hola".into(), 4);
+        let c = StandardChromosome::new("hola".into(), 4).unwrap();
         let g1 = Box::new(Gene::new('h'));
         let g2 = Box::new(Gene::new('a'));
         let g3 = Box::new(Gene::new('l'));
         let g4 = Box::new(Gene::new('o'));
-        let mut v = vec![];
-        v.push(g1);
-        v.push(g2);
-        v.push(g3);
-        v.push(g4);
+        let v = vec![g1, g2, g3, g4];
 
         assert_eq!(c.fitness(&v), 2);
     }
 
     #[test]
     fn test_fitness3() {
-        let c = StandardChromosome::new("hola".into(), 4);
+        let c = StandardChromosome::new("hola".into(), 4).unwrap();
         let g1 = Box::new(Gene::new('h'));
         let g2 = Box::new(Gene::new('o'));
         let g3 = Box::new(Gene::new('l'));
         let g4 = Box::new(Gene::new('a'));
-        let mut v = vec![];
-        v.push(g1);
-        v.push(g2);
-        v.push(g3);
-        v.push(g4);
+        let v = vec![g1, g2, g3, g4];
+
+        assert_eq!(c.fitness(&v), 0);
+    }
+
+    #[test]
+    fn test_with_seed_replays_run() {
+        use std::sync::{Arc, Mutex};
+
+        let run = |seed: u64| {
+            let trace = Arc::new(Mutex::new(vec![]));
+            let t = trace.clone();
+            let mut c = EvolvingChromosome::new("hola".into(), 20)
+                .unwrap()
+                .with_seed(seed)
+                .with_mr(0.1);
+
+            c.on_evolve_iteration.connect(move |it, bf, _, chromosome| {
+                t.lock().unwrap().push((it, bf, chromosome.get_genes()));
+            });
+            c.evolve();
+
+            trace.lock().unwrap().clone()
+        };
+
+        assert_eq!(run(11), run(11));
+    }
+
+    #[test]
+    fn test_with_seed_sets_genes() {
+        let c1 = StandardChromosome::new("weasel".into(), 4)
+            .unwrap()
+            .with_seed(5);
+        let c2 = StandardChromosome::new("weasel".into(), 4)
+            .unwrap()
+            .with_seed(5);
+
+        assert_eq!(c1.seed(), 5);
+        assert_eq!(c1.get_genes(), c2.get_genes());
+    }
 
+    #[test]
+    fn test_with_fitness() {
+        use crate::libweasel::fitness::CaseInsensitiveHamming;
+
+        let c = StandardChromosome::new("hola".into(), 4).unwrap();
+        let v = vec![
+            Box::new(Gene::new('H')),
+            Box::new(Gene::new('O')),
+            Box::new(Gene::new('l')),
+            Box::new(Gene::new('a')),
+        ];
+        assert_eq!(c.fitness(&v), 2);
+
+        let c = c.with_fitness(Arc::new(CaseInsensitiveHamming));
+        assert_eq!(c.fitness_name(), "case-insensitive");
         assert_eq!(c.fitness(&v), 0);
     }
+
+    #[test]
+    fn test_evolve_with_levenshtein() {
+        use crate::libweasel::fitness::Levenshtein;
+
+        let mut c = EvolvingChromosome::new("hola".into(), 50)
+            .unwrap()
+            .with_seed(1)
+            .with_mr(0.1)
+            .with_fitness(Arc::new(Levenshtein));
+        c.evolve();
+
+        assert_eq!(c.get_genes(), "hola");
+    }
+
+    #[test]
+    fn test_variable_length_from_empty() {
+        let mut c = EvolvingChromosome::new("hola".into(), 100)
+            .unwrap()
+            .with_mr(0.1)
+            .with_seed(2)
+            .with_variable_length(0);
+        assert_eq!(c.size(), 0);
+        assert_eq!(c.fitness_name(), "levenshtein");
+
+        c.evolve();
+
+        assert_eq!(c.get_genes(), "hola");
+    }
+
+    #[test]
+    fn test_variable_length_shrinks() {
+        let mut c = EvolvingChromosome::new("hola".into(), 100)
+            .unwrap()
+            .with_mr(0.1)
+            .with_seed(3)
+            .with_variable_length(12);
+        assert_eq!(c.size(), 12);
+
+        c.evolve();
+
+        assert_eq!(c.size(), 4);
+        assert_eq!(c.get_genes(), "hola");
+    }
+
+    #[test]
+    fn test_evolve_with_selection() {
+        use crate::libweasel::selection::Tournament;
+
+        let mut c = EvolvingChromosome::new("hola".into(), 50)
+            .unwrap()
+            .with_seed(6)
+            .with_mr(0.1)
+            .with_selection(Arc::new(Tournament::new(10)));
+        assert_eq!(c.selection_name(), "tournament");
+
+        c.evolve();
+
+        assert_eq!(c.get_genes(), "hola");
+    }
+
+    /// Best fitness of every generation of a (1,λ) or (1+λ) run.
+    /// The best fitness of every generation of a run to "hola", and the
+    /// chromosome at its end.
+    fn trace(
+        replacement: Replacement,
+        elitism: usize,
+        latching: bool,
+    ) -> (Vec<u32>, EvolvingChromosome) {
+        use std::sync::{Arc, Mutex};
+
+        let trace = Arc::new(Mutex::new(vec![]));
+        let t = trace.clone();
+        let mut c = EvolvingChromosome::new("hola".into(), 5)
+            .unwrap()
+            .with_seed(12)
+            .with_mr(0.2)
+            .with_replacement(replacement)
+            .with_elitism(elitism)
+            .with_latching(latching);
+
+        c.on_evolve_iteration
+            .connect(move |_, bf, _, _| t.lock().unwrap().push(bf));
+        c.evolve();
+
+        let t = trace.lock().unwrap().clone();
+        (t, c)
+    }
+
+    #[test]
+    fn test_plus_never_gets_worse() {
+        let (t, _) = trace(Replacement::Plus, 0, false);
+
+        assert!(t.windows(2).all(|w| w[1] <= w[0]));
+        assert_eq!(t.last(), Some(&0));
+    }
+
+    #[test]
+    fn test_comma_can_get_worse() {
+        let (t, _) = trace(Replacement::Comma, 0, false);
+
+        assert!(t.windows(2).any(|w| w[1] > w[0]));
+        assert_eq!(t.last(), Some(&0));
+    }
+
+    #[test]
+    fn test_comma_with_elitism_never_gets_worse() {
+        let (t, _) = trace(Replacement::Comma, 1, false);
+
+        assert!(t.windows(2).all(|w| w[1] <= w[0]));
+    }
+
+    #[test]
+    fn test_plus_elitism_keeps_parents_unique() {
+        // Children mutating every gene are always worse, so the parents
+        // stay put; none of them may be taken twice.
+        let mut c = EvolvingChromosome::new("Me thinks it's like a weasel".into(), 10)
+            .unwrap()
+            .with_seed(30)
+            .with_mr(1.0)
+            .with_mu(3)
+            .with_replacement(Replacement::Plus)
+            .with_elitism(1);
+
+        for _ in 0..5 {
+            c.step();
+            let run = c.run.as_ref().unwrap();
+            let genes: Vec<String> = run.parents.iter().map(|p| format!("{p:?}")).collect();
+            assert_eq!(genes.len(), 3);
+            assert!(
+                genes
+                    .iter()
+                    .enumerate()
+                    .all(|(i, g)| !genes[..i].contains(g))
+            );
+        }
+    }
+
+    #[test]
+    fn test_plus_elitism_keeps_mu_parents() {
+        use crate::libweasel::selection::Tournament;
+
+        // Tournaments pick the same individual more than once, so they
+        // would pick the elite again too if it took part.
+        let mut c = EvolvingChromosome::new("Me thinks it's like a weasel".into(), 6)
+            .unwrap()
+            .with_seed(31)
+            .with_mr(1.0)
+            .with_mu(4)
+            .with_selection(Arc::new(Tournament::new(5)))
+            .with_replacement(Replacement::Plus)
+            .with_elitism(2);
+        let genes = |c: &EvolvingChromosome| -> Vec<String> {
+            let run = c.run.as_ref().unwrap();
+            assert_eq!((run.pfit.len(), run.pmr.len()), (4, 4));
+            run.parents.iter().map(|p| format!("{p:?}")).collect()
+        };
+
+        c.step();
+        for _ in 0..20 {
+            let elite = genes(&c)[..2].to_vec();
+            c.step();
+            let parents = genes(&c);
+            assert_eq!(parents.len(), 4);
+            // The elite are kept once, unless they were twins already.
+            let count = |v: &[String], g: &String| v.iter().filter(|&p| p == g).count();
+            for e in &elite {
+                assert!(count(&parents, e) <= count(&elite, e));
+            }
+        }
+    }
+
+    #[test]
+    fn test_mu_parents() {
+        let mut c = EvolvingChromosome::new("hola".into(), 30)
+            .unwrap()
+            .with_seed(13)
+            .with_mr(0.1)
+            .with_mu(5)
+            .with_replacement(Replacement::Comma)
+            .with_elitism(2);
+        assert_eq!(c.mu(), 5);
+
+        c.evolve();
+
+        assert_eq!(c.get_genes(), "hola");
+    }
+
+    #[test]
+    fn test_chromosome_is_send_sync() {
+        fn assert_send_sync<C: Send + Sync>() {}
+
+        assert_send_sync::<StandardChromosome>();
+        assert_send_sync::<EvolvingChromosome>();
+    }
+
+    #[test]
+    fn test_evolve_on_another_thread() {
+        let c = EvolvingChromosome::new("hola".into(), 50)
+            .unwrap()
+            .with_seed(14)
+            .with_mr(0.1);
+
+        let genes = std::thread::spawn(move || {
+            let mut c = c;
+            c.evolve();
+            c.get_genes()
+        })
+        .join()
+        .unwrap();
+
+        assert_eq!(genes, "hola");
+    }
+
+    #[test]
+    fn test_evolve_termination() {
+        // Without mutations the children are copies of the parent, so the
+        // target can never be reached.
+        let mut c = EvolvingChromosome::new("hola".into(), 20)
+            .unwrap()
+            .with_seed(15)
+            .with_termination(Termination::new().with_max_generations(50));
+        let bf = c.fitness(&c.gene_list);
+        assert!(bf > 0);
+        assert_eq!(c.evolve().reason, TerminationReason::MaxGenerations);
+
+        let mut c = c.with_termination(Termination::new().with_stagnation(Some(30)));
+        assert_eq!(c.evolve().reason, TerminationReason::Stagnation);
+
+        let mut c = c.with_termination(Termination::new().with_target_fitness(bf));
+        assert_eq!(c.evolve().reason, TerminationReason::TargetReached);
+    }
+
+    #[test]
+    fn test_unicode_target() {
+        let mut c = EvolvingChromosome::new("ñoño".into(), 4).unwrap();
+        assert_eq!(c.target_len(), 4);
+        assert_eq!(c.size(), 4);
+
+        c.create_genes_from_target();
+        assert_eq!(c.fitness(&c.gene_list), 0);
+        assert_eq!(c.get_genes_colored(), "ñoño");
+
+        // 'n' + U+0303 COMBINING TILDE is the same target as 'ñ'.
+        let c = EvolvingChromosome::new("n\u{303}u".into(), 4).unwrap();
+        assert_eq!(c.target(), "ñu");
+        assert_eq!(c.size(), 2);
+    }
+
+    #[test]
+    fn test_charset() {
+        let dna = Charset::from_name("dna").unwrap();
+        let mut c = EvolvingChromosome::new_with_charset("GATTACA".into(), 20, dna)
+            .unwrap()
+            .with_seed(3)
+            .with_mr(0.1);
+        assert!(c.get_genes().chars().all(|g| "ACGT".contains(g)));
+
+        c.evolve();
+        assert_eq!(c.get_genes(), "GATTACA");
+
+        let e = StandardChromosome::new_with_charset("0110".into(), 4, Charset::default()).err();
+        assert_eq!(e, None);
+        let e = StandardChromosome::new_with_charset(
+            "hola".into(),
+            4,
+            Charset::from_name("binary").unwrap(),
+        )
+        .err();
+        assert!(matches!(e, Some(WeaselError::InvalidTarget(_))));
+    }
+
+    #[test]
+    fn test_invalid_target() {
+        let e = StandardChromosome::new("hola@mundo!".into(), 4).err();
+
+        assert_eq!(
+            e,
+            Some(WeaselError::InvalidTarget(vec![(4, '@'), (10, '!')]))
+        );
+    }
+
+    #[test]
+    fn test_evolve_cancelled() {
+        use crate::libweasel::termination::CancelFlag;
+        use std::sync::atomic::Ordering;
+
+        let flag = CancelFlag::default();
+        let f = flag.clone();
+        let mut c = EvolvingChromosome::new("Me thinks it's like a weasel".into(), 10)
+            .unwrap()
+            .with_seed(16)
+            .with_mr(0.05)
+            .with_termination(Termination::new().with_cancel_flag(flag));
+
+        c.on_evolve_iteration.connect(move |it, _, _, _| {
+            if it == 3 {
+                f.store(true, Ordering::Relaxed);
+            }
+        });
+
+        assert_eq!(c.evolve().reason, TerminationReason::Cancelled);
+    }
+
+    #[test]
+    fn test_one_max() {
+        let mut c = EvolvingChromosome::from_alleles(vec![true; 64], 20, ())
+            .unwrap()
+            .with_seed(17)
+            .with_mr(1.0 / 64.0);
+        assert_eq!(c.get_genes().len(), 64);
+
+        assert_eq!(c.evolve().reason, TerminationReason::TargetReached);
+        assert_eq!(c.get_alleles(), vec![true; 64]);
+    }
+
+    #[test]
+    fn test_integer_target() {
+        let target: Vec<u8> = vec![192, 168, 1, 254];
+        let mut c = EvolvingChromosome::from_alleles(target.clone(), 100, 0..=255)
+            .unwrap()
+            .with_seed(18)
+            .with_mr(0.5);
+
+        c.evolve();
+        assert_eq!(c.get_alleles(), target);
+        assert_eq!(c.target(), "192 168 1 254");
+
+        let e = EvolvingChromosome::from_alleles(vec![3, 12, 7], 4, 0..=9).err();
+        assert_eq!(e, Some(WeaselError::OutOfDomain(vec![1])));
+    }
+
+    #[test]
+    fn test_real_target() {
+        use crate::libweasel::allele::Gaussian;
+        use crate::libweasel::fitness::AbsoluteError;
+
+        let target = vec![0.5, -1.25, 2.0];
+        let mut c =
+            EvolvingChromosome::from_alleles(target.clone(), 20, Gaussian::new(-5.0, 5.0, 0.1))
+                .unwrap()
+                .with_seed(19)
+                .with_mr(0.5)
+                .with_fitness(Arc::new(AbsoluteError::new(1000.0)))
+                .with_termination(Termination::new().with_target_fitness(30));
+
+        assert_eq!(c.evolve().reason, TerminationReason::TargetReached);
+        let error: f64 = c
+            .get_alleles()
+            .iter()
+            .zip(&target)
+            .map(|(g, t)| (g - t).abs())
+            .sum();
+        assert!(error <= 0.03);
+    }
+
+    #[test]
+    fn test_latching_never_gets_worse() {
+        let (t, c) = trace(Replacement::Comma, 0, true);
+
+        assert!(c.latching());
+        assert!(t.windows(2).all(|w| w[1] <= w[0]));
+        assert_eq!(t.last(), Some(&0));
+        assert!((0..4).all(|i| c[i].is_locked()));
+    }
+
+    #[test]
+    fn test_gene_mrs() {
+        let mut c = EvolvingChromosome::new("hola".into(), 20)
+            .unwrap()
+            .with_seed(20)
+            .with_mr(0.0)
+            .with_gene_mrs(vec![0.5, 0.5])
+            .with_termination(Termination::new().with_max_generations(200));
+        let before = c.get_alleles();
+        c.evolve();
+        let after = c.get_alleles();
+
+        assert_eq!(&after[..2], ['h', 'o']);
+        assert_eq!(after[2..], before[2..]);
+        assert_eq!(c[0].mr(), Some(0.5));
+        assert_eq!(c[3].mr(), None);
+    }
+
+    /// Mutation rate of every generation of a run adapted with `a`.
+    fn mr_trace(a: MrAdaptation) -> Vec<f64> {
+        use std::sync::Mutex;
+
+        let trace = Arc::new(Mutex::new(vec![]));
+        let t = trace.clone();
+        let mut c = EvolvingChromosome::new("Me thinks it's like a weasel".into(), 50)
+            .unwrap()
+            .with_seed(21)
+            .with_mr(0.2)
+            .with_mr_adaptation(a);
+        assert_eq!(c.mr_adaptation(), a);
+
+        c.on_evolve_iteration
+            .connect(move |_, _, mr, _| t.lock().unwrap().push(mr));
+        assert_eq!(c.evolve().reason, TerminationReason::TargetReached);
+        assert_eq!(Some(&c.mr()), trace.lock().unwrap().last());
+
+        trace.lock().unwrap().clone()
+    }
+
+    #[test]
+    fn test_mr_adaptation() {
+        use crate::libweasel::adaptation::MAX_MR;
+
+        for a in [
+            MrAdaptation::one_fifth(),
+            MrAdaptation::rechenberg(),
+            MrAdaptation::self_adaptive(),
+        ] {
+            let t = mr_trace(a);
+            assert!(t.iter().all(|mr| (1.0 / 28.0..=MAX_MR).contains(mr)));
+            // Close to the target most mutations are harmful, so the rate goes down.
+            assert!(t.last().unwrap() < &0.2, "{}", a.name());
+        }
+    }
+
+    #[test]
+    fn test_mutation_operators() {
+        use crate::libweasel::mutation::{self, ExactlyK};
+
+        let c = EvolvingChromosome::new("hola mundo".into(), 1)
+            .unwrap()
+            .with_seed(22)
+            .with_mutation(Arc::new(ExactlyK::new(2)));
+        assert_eq!(c.mutation_name(), "exactly");
+        let mut rng = rng::from_seed(0);
+        for _ in 0..50 {
+            let child = c.mutate_genes(&c.gene_list, &mut rng);
+            let changed = child
+                .iter()
+                .zip(&c.gene_list)
+                .filter(|(a, b)| a.get() != b.get())
+                .count();
+            assert!(changed <= 2);
+        }
+
+        for name in mutation::MUTATION_NAMES {
+            let mut c = EvolvingChromosome::new("hola mundo".into(), 50)
+                .unwrap()
+                .with_seed(23)
+                .with_mr(0.1)
+                .with_mutation(mutation::from_name(name).unwrap());
+
+            assert_eq!(
+                c.evolve().reason,
+                TerminationReason::TargetReached,
+                "{name}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_evolution_result() {
+        let mut c = EvolvingChromosome::new("hola".into(), 20)
+            .unwrap()
+            .with_seed(24)
+            .with_mr(0.1)
+            .with_history(true);
+        let r = c.evolve();
+
+        assert!(r.target_reached());
+        assert_eq!(r.best_fitness, 0);
+        assert_eq!(r.genes, vec!['h', 'o', 'l', 'a']);
+        assert_eq!(r.genes_string, c.get_genes());
+        assert!(r.evaluations > r.generations as u64);
+        assert!(r.evaluations <= 1 + 20 * r.generations as u64);
+
+        let h = r.history.unwrap();
+        assert_eq!(h.len(), r.generations as usize);
+        assert!(h.windows(2).all(|w| w[1].best_fitness <= w[0].best_fitness));
+        assert_eq!(h.last().unwrap().best_fitness, 0);
+
+        let mut c = c.with_history(false);
+        assert_eq!(c.evolve().history, None);
+    }
+
+    #[test]
+    fn test_step() {
+        let mut c = EvolvingChromosome::new("hola".into(), 20)
+            .unwrap()
+            .with_seed(25)
+            .with_mr(0.1);
+
+        let s1 = c.step();
+        let s2 = c.step();
+        assert_eq!((s1.generation, s2.generation), (1, 2));
+        assert!(s2.best_fitness <= s1.best_fitness);
+        assert_eq!(s2.genes, c.get_alleles());
+        assert_eq!(s2.evaluations, 1 + 2 * 20);
+        assert!(s2.best_fitness as f64 <= s2.mean_fitness);
+        assert!(s2.mean_fitness <= s2.worst_fitness as f64);
+        assert!(s2.elapsed >= s1.elapsed);
+
+        // evolve starts a run of its own.
+        let r = c.evolve();
+        assert!(r.target_reached());
+        assert_eq!(c.step().generation, r.generations + 1);
+    }
+
+    #[test]
+    fn test_evolution_iterator() {
+        let new = || {
+            EvolvingChromosome::new("hola".into(), 20)
+                .unwrap()
+                .with_seed(26)
+                .with_mr(0.1)
+        };
+
+        let mut c = new();
+        let first: Vec<u32> = c.evolution().take(3).map(|s| s.generation).collect();
+        assert_eq!(first, vec![1, 2, 3]);
+
+        // The iterator goes on with the same run, up to the target.
+        let rest: Vec<Snapshot> = c.evolution().collect();
+        let last = rest.last().unwrap();
+        assert_eq!(rest[0].generation, 4);
+        assert_eq!(last.reason, Some(TerminationReason::TargetReached));
+        assert!(rest[..rest.len() - 1].iter().all(|s| s.reason.is_none()));
+        assert_eq!(c.get_genes(), "hola");
+
+        // Stepping or evolving, the run is the same.
+        let r = new().evolve();
+        assert_eq!(r.generations, last.generation);
+        assert_eq!(r.evaluations, last.evaluations);
+    }
+
+    #[test]
+    fn test_signals() {
+        use std::sync::Mutex;
+
+        let mut c = EvolvingChromosome::new("Me thinks".into(), 20)
+            .unwrap()
+            .with_seed(27)
+            .with_mr(0.05);
+        let starts = Arc::new(Mutex::new(vec![]));
+        let improvements = Arc::new(Mutex::new(vec![]));
+        let children = Arc::new(Mutex::new(0u64));
+        let stagnant = Arc::new(Mutex::new(0u32));
+        let finish = Arc::new(Mutex::new(None));
+
+        let t = starts.clone();
+        c.on_start.connect(move |bf, _| t.lock().unwrap().push(bf));
+        let t = improvements.clone();
+        c.on_improvement
+            .connect(move |it, old, bf, _| t.lock().unwrap().push((it, old, bf)));
+        let t = children.clone();
+        c.on_child_evaluated
+            .connect(move |_, _| *t.lock().unwrap() += 1);
+        let t = stagnant.clone();
+        c.on_stagnation.connect(move |_, n| {
+            assert!(n > 0);
+            *t.lock().unwrap() += 1;
+        });
+        let t = finish.clone();
+        c.on_finish.connect(move |r| *t.lock().unwrap() = Some(r));
+
+        let r = c.evolve();
+        let improvements = improvements.lock().unwrap();
+
+        // Improvements only, chained one after the other down to the target.
+        assert_eq!(starts.lock().unwrap().len(), 1);
+        assert_eq!(improvements[0].1, starts.lock().unwrap()[0]);
+        assert!(improvements.iter().all(|&(_, old, bf)| bf < old));
+        assert!(improvements.windows(2).all(|w| w[0].2 == w[1].1));
+        let &(it, _, bf) = improvements.last().unwrap();
+        assert_eq!((it, bf), (r.generations, 0));
+        assert_eq!(
+            improvements.len() as u32 + *stagnant.lock().unwrap(),
+            r.generations
+        );
+        assert_eq!(*children.lock().unwrap() + 1, r.evaluations);
+        assert_eq!(finish.lock().unwrap().as_ref(), Some(&r));
+    }
+
+    #[test]
+    fn test_whole_brood_is_scored() {
+        let mut c = EvolvingChromosome::new("hola".into(), 100)
+            .unwrap()
+            .with_seed(32)
+            .with_mr(0.2);
+        let r = c.evolve();
+
+        // The same with and without the parallel feature.
+        assert!(r.target_reached());
+        assert_eq!(r.evaluations, 1 + 100 * r.generations as u64);
+    }
+
+    #[test]
+    fn test_request_cancel() {
+        let mut c = EvolvingChromosome::new("Me thinks it's like a weasel".into(), 10)
+            .unwrap()
+            .with_seed(28)
+            .with_mr(0.05);
+
+        c.on_evolve_iteration.connect(|it, _, _, chromosome| {
+            if it == 3 {
+                chromosome.request_cancel();
+            }
+        });
+
+        let r = c.evolve();
+        assert_eq!((r.reason, r.generations), (TerminationReason::Cancelled, 3));
+
+        // A request made between runs, even through a copy, is dropped
+        // when the next run starts.
+        let mut c = c.with_termination(Termination::new().with_max_generations(5));
+        c.on_evolve_iteration.clear();
+        c.clone().request_cancel();
+        assert!(c.cancel_flag().load(Ordering::Relaxed));
+        assert_eq!(c.evolve().reason, TerminationReason::MaxGenerations);
+
+        // Copies made before the run share its flag.
+        let copy = c.clone();
+        c.on_evolve_iteration.connect(move |it, _, _, _| {
+            if it == 2 {
+                copy.request_cancel();
+            }
+        });
+        let r = c.evolve();
+        assert_eq!((r.reason, r.generations), (TerminationReason::Cancelled, 2));
+
+        // A flag of the `Termination` is never lowered, even when raised
+        // before the run.
+        let flag = CancelFlag::default();
+        flag.store(true, Ordering::Relaxed);
+        let mut c = c.with_termination(Termination::new().with_cancel_flag(flag.clone()));
+        c.on_evolve_iteration.clear();
+        let r = c.evolve();
+        assert_eq!((r.reason, r.generations), (TerminationReason::Cancelled, 1));
+        assert!(flag.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn test_encoded_genes() {
+        assert_eq!(encode("Me thinks"), "Me thinks");
+        assert_eq!(encode("ñandú\\\t!"), "\\u{f1}and\\u{fa}\\\\\\u{9}!");
+
+        let c = EvolvingChromosome::new("año".into(), 1)
+            .unwrap()
+            .with_seed(29);
+        assert_eq!(c.get_genes_encoded(), encode(&c.get_genes()));
+        assert!(
+            c.get_genes_encoded()
+                .chars()
+                .all(|c| c.is_ascii_graphic() || c == ' ')
+        );
+    }
 }
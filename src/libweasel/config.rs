@@ -0,0 +1,120 @@
+// Copyright (C) 2025  Antonio-Miguel Corbi Bellot
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use crate::libweasel::error::{Result, WeaselError};
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+// -- Classes: ------------------------------------------------------------
+
+/// A run described in a TOML file, as given with `--config`.  Keys are
+/// the long names of the flags of `run` and all of them are optional:
+///
+/// ```toml
+/// sentence = "Me thinks it's like a weasel"
+/// charset = "weasel"
+/// mrate = 0.05
+/// mutation = "exactly:1"
+/// selection = "tournament:3"
+/// population = 200
+/// max-generations = 5000
+/// seed = 42
+/// format = "csv"
+/// ```
+///
+/// Values are only checked to be of the right type here; `Arguments`
+/// checks them as it does with flags when taking them.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct RunConfig {
+    pub sentence: Option<String>,
+    pub mrate: Option<f64>,
+    pub ncopies: Option<u32>,
+    pub encoded: Option<bool>,
+    pub format: Option<String>,
+    pub seed: Option<u64>,
+    pub fitness: Option<String>,
+    pub charset: Option<String>,
+    pub distribution: Option<String>,
+    pub length: Option<usize>,
+    pub population: Option<usize>,
+    pub crossover: Option<String>,
+    pub selection: Option<String>,
+    pub mu: Option<usize>,
+    pub replacement: Option<String>,
+    pub elitism: Option<usize>,
+    pub latching: Option<bool>,
+    pub mr_adaptation: Option<String>,
+    pub mutation: Option<String>,
+    pub max_generations: Option<u32>,
+    pub timeout: Option<f64>,
+    pub target_fitness: Option<u32>,
+    pub stagnation: Option<u32>,
+}
+
+// -- Impl. blocks: -------------------------------------------------------
+
+impl RunConfig {
+    /// The run described by the TOML in `text`.
+    pub fn from_toml(text: &str) -> Result<Self> {
+        toml::from_str(text).map_err(|e| WeaselError::Config(e.message().to_owned()))
+    }
+
+    /// The run described by the TOML file at `path`.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)
+            .map_err(|e| WeaselError::Io(format!("{}: {e}", path.display())))?;
+
+        toml::from_str(&text).map_err(|e| WeaselError::Config(format!("{}: {e}", path.display())))
+    }
+}
+
+// -- Tests: --------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_toml() {
+        let c = RunConfig::from_toml(
+            "sentence = \"hola\"\n\
+             mrate = 0.1\n\
+             max-generations = 50\n\
+             latching = true\n",
+        )
+        .unwrap();
+
+        assert_eq!(c.sentence.as_deref(), Some("hola"));
+        assert_eq!((c.mrate, c.max_generations), (Some(0.1), Some(50)));
+        assert_eq!((c.latching, c.seed), (Some(true), None));
+        assert_eq!(RunConfig::from_toml("").unwrap(), RunConfig::default());
+    }
+
+    #[test]
+    fn bad_toml() {
+        let e = |text| RunConfig::from_toml(text).unwrap_err();
+
+        assert!(e("nope = 1").to_string().contains("unknown field `nope`"));
+        assert!(matches!(e("ncopies = \"ten\""), WeaselError::Config(_)));
+        assert!(matches!(e("seed = -1"), WeaselError::Config(_)));
+        assert!(matches!(e("sentence = "), WeaselError::Config(_)));
+        assert!(matches!(
+            RunConfig::from_file("/nonexistent/run.toml"),
+            Err(WeaselError::Io(_))
+        ));
+    }
+}
@@ -0,0 +1,369 @@
+// Copyright (C) 2025  Antonio-Miguel Corbi Bellot
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use crate::libweasel::charset::Charset;
+use crate::libweasel::selection::{cumulative, spin};
+use rand::{Rng, RngCore};
+use std::sync::Arc;
+
+/// Rows of a Spanish QWERTY keyboard, used by `NearbyKey`.
+const KEYBOARD: [&str; 4] = ["1234567890", "qwertyuiop", "asdfghjklñ", "zxcvbnm,.-"];
+
+/// Relative frequencies of the English letters, in percent.
+const ENGLISH: [(char, f64); 26] = [
+    ('e', 12.7),
+    ('t', 9.1),
+    ('a', 8.2),
+    ('o', 7.5),
+    ('i', 7.0),
+    ('n', 6.7),
+    ('s', 6.3),
+    ('h', 6.1),
+    ('r', 6.0),
+    ('d', 4.3),
+    ('l', 4.0),
+    ('c', 2.8),
+    ('u', 2.8),
+    ('m', 2.4),
+    ('w', 2.4),
+    ('f', 2.2),
+    ('g', 2.0),
+    ('y', 2.0),
+    ('p', 1.9),
+    ('b', 1.5),
+    ('v', 1.0),
+    ('k', 0.8),
+    ('j', 0.15),
+    ('x', 0.15),
+    ('q', 0.1),
+    ('z', 0.07),
+];
+
+// -- Traits: -------------------------------------------------------------
+
+/// What a gene turns into when it mutates.  Initial genes are always
+/// drawn uniformly from the charset, distributions only shape mutations.
+pub trait MutationDistribution: Send + Sync {
+    /// The char that replaces `current`, one of `cs`.
+    fn mutate(&self, current: char, cs: &Charset, rng: &mut dyn RngCore) -> char;
+    fn name(&self) -> &'static str;
+}
+
+// -- Classes: ------------------------------------------------------------
+
+/// Any char of the charset, all of them equally likely.
+#[derive(Clone, Debug, Default)]
+pub struct Uniform;
+
+/// Every char of the charset has its own probability.
+#[derive(Clone, Debug)]
+pub struct Weighted {
+    chars: Vec<char>,
+    wheel: Vec<f64>,
+}
+
+/// A key next to the current one on the keyboard, as a typist would
+/// mistype it, with probability `p`.  Otherwise, or if the current char
+/// is off the keyboard, any char of the charset.
+#[derive(Clone, Debug)]
+pub struct NearbyKey {
+    p: f64,
+}
+
+/// A char whose code point is at most `k` away from the current one.
+#[derive(Clone, Debug)]
+pub struct Drift {
+    k: u32,
+}
+
+// -- Impl. blocks: -------------------------------------------------------
+
+impl MutationDistribution for Uniform {
+    fn mutate(&self, _current: char, cs: &Charset, rng: &mut dyn RngCore) -> char {
+        cs.rand_char(rng)
+    }
+
+    fn name(&self) -> &'static str {
+        "uniform"
+    }
+}
+
+impl Weighted {
+    /// Char `c` of `cs` weighs `weight(c)`.  Chars of other charsets are
+    /// never drawn.
+    pub fn new(cs: &Charset, weight: impl Fn(char) -> f64) -> Self {
+        let chars: Vec<char> = cs.chars().collect();
+        let wheel = cumulative(chars.iter().map(|&c| weight(c).max(0.0)));
+
+        Weighted { chars, wheel }
+    }
+
+    /// English letter frequencies, capitals being ten times rarer than
+    /// small letters.  The space is as frequent as in English text and
+    /// any other char weighs as much as the rarest letter.
+    pub fn english(cs: &Charset) -> Self {
+        Weighted::new(cs, |c| {
+            let lower = c.to_lowercase().next().unwrap_or(c);
+            let w = ENGLISH.iter().find(|(l, _)| *l == lower).map(|(_, w)| *w);
+
+            match (c, w) {
+                (' ', _) => 18.0,
+                (_, Some(w)) if c.is_uppercase() => w / 10.0,
+                (_, Some(w)) => w,
+                (_, None) => 0.07,
+            }
+        })
+    }
+}
+
+impl MutationDistribution for Weighted {
+    fn mutate(&self, _current: char, cs: &Charset, rng: &mut dyn RngCore) -> char {
+        match self.wheel.last() {
+            Some(&total) if total > 0.0 => self.chars[spin(&self.wheel, rng)],
+            _ => cs.rand_char(rng),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "weighted"
+    }
+}
+
+impl NearbyKey {
+    /// `p` is kept within [0, 1].  A NaN one never picks a nearby key.
+    pub fn new(p: f64) -> Self {
+        NearbyKey {
+            p: if p.is_nan() { 0.0 } else { p.clamp(0.0, 1.0) },
+        }
+    }
+
+    /// Keys around `c` on a staggered keyboard, in the same case as `c`.
+    fn neighbours(c: char) -> Vec<char> {
+        let upper = c.is_uppercase();
+        let lower = c.to_lowercase().next().unwrap_or(c);
+        let rows: Vec<Vec<char>> = KEYBOARD.iter().map(|r| r.chars().collect()).collect();
+
+        let Some((row, col)) = rows
+            .iter()
+            .enumerate()
+            .find_map(|(i, r)| r.iter().position(|&k| k == lower).map(|j| (i, j)))
+        else {
+            return vec![];
+        };
+
+        // Every row is shifted half a key to the right of the one above.
+        let col = col as isize;
+        let around = [
+            (row as isize - 1, col),
+            (row as isize - 1, col + 1),
+            (row as isize, col - 1),
+            (row as isize, col + 1),
+            (row as isize + 1, col - 1),
+            (row as isize + 1, col),
+        ];
+
+        around
+            .iter()
+            .filter_map(|&(i, j)| {
+                rows.get(usize::try_from(i).ok()?)?
+                    .get(usize::try_from(j).ok()?)
+            })
+            .map(|&k| {
+                if upper {
+                    k.to_uppercase().next().unwrap_or(k)
+                } else {
+                    k
+                }
+            })
+            .collect()
+    }
+}
+
+impl Default for NearbyKey {
+    fn default() -> Self {
+        NearbyKey::new(0.9)
+    }
+}
+
+impl MutationDistribution for NearbyKey {
+    fn mutate(&self, current: char, cs: &Charset, rng: &mut dyn RngCore) -> char {
+        let keys: Vec<char> = Self::neighbours(current)
+            .into_iter()
+            .filter(|&k| cs.contains(k))
+            .collect();
+
+        if keys.is_empty() || !rng.random_bool(self.p) {
+            cs.rand_char(rng)
+        } else {
+            keys[rng.random_range(0..keys.len())]
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "keyboard"
+    }
+}
+
+impl Drift {
+    pub fn new(k: u32) -> Self {
+        Drift { k: k.max(1) }
+    }
+}
+
+impl Default for Drift {
+    fn default() -> Self {
+        Drift::new(1)
+    }
+}
+
+impl MutationDistribution for Drift {
+    fn mutate(&self, current: char, cs: &Charset, rng: &mut dyn RngCore) -> char {
+        let c = current as u32;
+        let near: Vec<char> = cs
+            .within(c.saturating_sub(self.k), c.saturating_add(self.k))
+            .iter()
+            .copied()
+            .filter(|&n| n != current)
+            .collect();
+
+        if near.is_empty() {
+            cs.rand_char(rng)
+        } else {
+            near[rng.random_range(0..near.len())]
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "drift"
+    }
+}
+
+/// Names accepted by `from_name`.
+pub const DISTRIBUTION_NAMES: [&str; 4] = ["uniform", "english", "keyboard", "drift"];
+
+/// Builds one of the shipped distributions over `cs` from its name.  The
+/// `p` of keyboard and the `k` of drift can follow a colon, e.g.
+/// `keyboard:0.8` or `drift:3`.  `p` must be a finite number.
+pub fn from_name(name: &str, cs: &Charset) -> Option<Arc<dyn MutationDistribution>> {
+    let (name, param) = match name.split_once(':') {
+        Some((n, p)) => (n, Some(p)),
+        None => (name, None),
+    };
+
+    match (name, param) {
+        ("uniform", None) => Some(Arc::new(Uniform)),
+        ("english", None) => Some(Arc::new(Weighted::english(cs))),
+        ("keyboard", None) => Some(Arc::new(NearbyKey::default())),
+        ("keyboard", Some(p)) => match p.parse::<f64>().ok()? {
+            p if p.is_finite() => Some(Arc::new(NearbyKey::new(p))),
+            _ => None,
+        },
+        ("drift", None) => Some(Arc::new(Drift::default())),
+        ("drift", Some(k)) => Some(Arc::new(Drift::new(k.parse().ok()?))),
+        _ => None,
+    }
+}
+
+// -- Tests: --------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::libweasel::rng;
+
+    /// `n` mutations of `current`.
+    fn mutations(d: &dyn MutationDistribution, current: char, cs: &Charset, n: usize) -> String {
+        let mut rng = rng::from_seed(21);
+
+        (0..n).map(|_| d.mutate(current, cs, &mut rng)).collect()
+    }
+
+    #[test]
+    fn weighted() {
+        let cs = Charset::from_name("lowercase").unwrap();
+        let m = mutations(&Weighted::english(&cs), 'a', &cs, 5000);
+        let count = |c| m.chars().filter(|&x| x == c).count();
+
+        assert!(count('e') > count('k'));
+        assert!(count(' ') > count('e'));
+        assert!(m.chars().all(|c| cs.contains(c)));
+
+        let only_b = Weighted::new(&cs, |c| if c == 'b' { 1.0 } else { 0.0 });
+        assert!(mutations(&only_b, 'a', &cs, 100).chars().all(|c| c == 'b'));
+    }
+
+    #[test]
+    fn nearby_key() {
+        let cs = Charset::default();
+        let always = NearbyKey::new(1.0);
+
+        assert!(
+            mutations(&always, 'g', &cs, 200)
+                .chars()
+                .all(|c| "tyfhvb".contains(c))
+        );
+        assert!(
+            mutations(&always, 'Q', &cs, 200)
+                .chars()
+                .all(|c| "12WA".contains(c))
+        );
+        // '?' is off the keyboard.
+        assert!(
+            mutations(&always, '?', &cs, 200)
+                .chars()
+                .any(|c| c.is_uppercase())
+        );
+        // Slips can reach chars off the keyboard too.
+        assert!(mutations(&NearbyKey::new(0.5), 'g', &cs, 200).contains(' '));
+
+        // Never a nearby key, but no panic either.
+        let nan = NearbyKey::new(f64::NAN);
+        assert!(
+            mutations(&nan, 'g', &cs, 200)
+                .chars()
+                .all(|c| cs.contains(c))
+        );
+        assert_eq!(nan.p, 0.0);
+    }
+
+    #[test]
+    fn drift() {
+        let cs = Charset::default();
+
+        assert!(
+            mutations(&Drift::new(2), 'm', &cs, 200)
+                .chars()
+                .all(|c| "klno".contains(c))
+        );
+        assert!(mutations(&Drift::new(1), 'ñ', &cs, 10).chars().count() == 10);
+
+        let dna = Charset::from_name("dna").unwrap();
+        assert_eq!(mutations(&Drift::new(2), 'A', &dna, 10), "CCCCCCCCCC");
+    }
+
+    #[test]
+    fn by_name() {
+        let cs = Charset::default();
+
+        for name in DISTRIBUTION_NAMES {
+            assert!(from_name(name, &cs).is_some());
+        }
+        assert_eq!(from_name("english", &cs).unwrap().name(), "weighted");
+        assert_eq!(from_name("drift:4", &cs).unwrap().name(), "drift");
+        assert!(from_name("drift:x", &cs).is_none());
+        assert!(from_name("keyboard:nan", &cs).is_none());
+        assert!(from_name("keyboard:inf", &cs).is_none());
+        assert!(from_name("nope", &cs).is_none());
+    }
+}
@@ -0,0 +1,86 @@
+// Copyright (C) 2025  Antonio-Miguel Corbi Bellot
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::fmt;
+
+// -- Classes: ------------------------------------------------------------
+
+/// Everything that can go wrong when setting up a weasel.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WeaselError {
+    /// The target has characters no gene can ever take, so it could never
+    /// be reached.  Each one comes with its position in the target.
+    InvalidTarget(Vec<(usize, char)>),
+    /// Like `InvalidTarget` for any other allele: the positions of the
+    /// target holding values outside the domain of the genes.
+    OutOfDomain(Vec<usize>),
+    /// A charset without a single char genes could take.
+    EmptyCharset,
+    /// A file could not be read.
+    Io(String),
+    /// A config file that isn't valid TOML, or has unknown keys or bad values.
+    Config(String),
+}
+
+pub type Result<T> = std::result::Result<T, WeaselError>;
+
+// -- Impl. blocks: -------------------------------------------------------
+
+impl fmt::Display for WeaselError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WeaselError::InvalidTarget(invalid) => {
+                let list: Vec<String> = invalid
+                    .iter()
+                    .map(|(i, c)| format!("{c:?} at position {i}"))
+                    .collect();
+                write!(
+                    f,
+                    "the target has characters outside the charset: {}",
+                    list.join(", ")
+                )
+            }
+            WeaselError::OutOfDomain(positions) => {
+                let list: Vec<String> = positions.iter().map(|i| i.to_string()).collect();
+                write!(
+                    f,
+                    "the target has values outside the gene domain at positions: {}",
+                    list.join(", ")
+                )
+            }
+            WeaselError::EmptyCharset => write!(f, "the charset has no characters"),
+            WeaselError::Io(msg) => write!(f, "{msg}"),
+            WeaselError::Config(msg) => write!(f, "{msg}"),
+        }
+    }
+}
+
+impl std::error::Error for WeaselError {}
+
+// -- Tests: --------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_target_message() {
+        let e = WeaselError::InvalidTarget(vec![(4, '@'), (7, '!')]);
+
+        assert_eq!(
+            e.to_string(),
+            "the target has characters outside the charset: '@' at position 4, '!' at position 7"
+        );
+    }
+}
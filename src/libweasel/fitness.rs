@@ -0,0 +1,305 @@
+// Copyright (C) 2025  Antonio-Miguel Corbi Bellot
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::sync::Arc;
+
+// -- Traits: -------------------------------------------------------------
+
+/// A way of scoring a gene sequence against the target.  The score is a
+/// distance: lower is better and 0 means the genes spell the target.
+/// Genes hold chars unless `A` says otherwise.
+pub trait Fitness<A = char>: Send + Sync {
+    fn fitness(&self, target: &[A], genes: &[A]) -> u32;
+    fn name(&self) -> &'static str;
+}
+
+// -- Classes: ------------------------------------------------------------
+
+/// Number of positions where genes and target differ.
+#[derive(Clone, Debug, Default)]
+pub struct Hamming;
+
+/// Like `Hamming`, but 'a' and 'A' count as the same letter.
+#[derive(Clone, Debug, Default)]
+pub struct CaseInsensitiveHamming;
+
+/// Minimum number of insertions, deletions and substitutions needed to
+/// turn the genes into the target.
+#[derive(Clone, Debug, Default)]
+pub struct Levenshtein;
+
+/// Number of characters left once the longest common prefix is removed,
+/// so the weasel is only rewarded for getting the target right from the
+/// left.
+#[derive(Clone, Debug, Default)]
+pub struct LongestCommonPrefix;
+
+/// `Hamming` where every position has its own weight.  Without explicit
+/// weights the first position weighs as much as the target is long and
+/// the last one weighs 1.
+#[derive(Clone, Debug, Default)]
+pub struct WeightedPosition {
+    weights: Vec<u32>,
+}
+
+/// Sum of the absolute differences between genes and target, times
+/// `scale` and rounded, for numeric genes.  A scale of 1000 tells reals
+/// apart down to the third decimal.
+#[derive(Clone, Debug)]
+pub struct AbsoluteError {
+    scale: f64,
+}
+
+// -- Impl. blocks: -------------------------------------------------------
+
+/// Differences beyond the shortest of both sequences.
+fn length_difference<A>(target: &[A], genes: &[A]) -> u32 {
+    target.len().abs_diff(genes.len()) as u32
+}
+
+impl<A: PartialEq> Fitness<A> for Hamming {
+    fn fitness(&self, target: &[A], genes: &[A]) -> u32 {
+        let d = target.iter().zip(genes).filter(|(t, g)| t != g).count() as u32;
+
+        d + length_difference(target, genes)
+    }
+
+    fn name(&self) -> &'static str {
+        "hamming"
+    }
+}
+
+impl Fitness for CaseInsensitiveHamming {
+    fn fitness(&self, target: &[char], genes: &[char]) -> u32 {
+        let d = target
+            .iter()
+            .zip(genes)
+            .filter(|(t, g)| !t.to_lowercase().eq(g.to_lowercase()))
+            .count() as u32;
+
+        d + length_difference(target, genes)
+    }
+
+    fn name(&self) -> &'static str {
+        "case-insensitive"
+    }
+}
+
+impl<A: PartialEq> Fitness<A> for Levenshtein {
+    fn fitness(&self, target: &[A], genes: &[A]) -> u32 {
+        // Only two rows of the classic dynamic programming table are kept.
+        let mut prev: Vec<u32> = (0..=genes.len() as u32).collect();
+        let mut curr: Vec<u32> = vec![0; genes.len() + 1];
+
+        for (i, t) in target.iter().enumerate() {
+            curr[0] = i as u32 + 1;
+            for (j, g) in genes.iter().enumerate() {
+                let substitution = prev[j] + u32::from(t != g);
+                let deletion = prev[j + 1] + 1;
+                let insertion = curr[j] + 1;
+                curr[j + 1] = substitution.min(deletion).min(insertion);
+            }
+            std::mem::swap(&mut prev, &mut curr);
+        }
+
+        prev[genes.len()]
+    }
+
+    fn name(&self) -> &'static str {
+        "levenshtein"
+    }
+}
+
+impl<A: PartialEq> Fitness<A> for LongestCommonPrefix {
+    fn fitness(&self, target: &[A], genes: &[A]) -> u32 {
+        let prefix = target.iter().zip(genes).take_while(|(t, g)| t == g).count();
+
+        (target.len().max(genes.len()) - prefix) as u32
+    }
+
+    fn name(&self) -> &'static str {
+        "prefix"
+    }
+}
+
+impl WeightedPosition {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Position `i` weighs `weights[i]`; positions past the end weigh 1.
+    pub fn with_weights(weights: Vec<u32>) -> Self {
+        WeightedPosition { weights }
+    }
+
+    fn weight(&self, i: usize, len: usize) -> u32 {
+        if self.weights.is_empty() {
+            len.saturating_sub(i).max(1) as u32
+        } else {
+            self.weights.get(i).copied().unwrap_or(1)
+        }
+    }
+}
+
+impl<A: PartialEq> Fitness<A> for WeightedPosition {
+    fn fitness(&self, target: &[A], genes: &[A]) -> u32 {
+        let len = target.len();
+        let d: u32 = target
+            .iter()
+            .zip(genes)
+            .enumerate()
+            .filter(|(_, (t, g))| t != g)
+            .map(|(i, _)| self.weight(i, len))
+            .sum();
+
+        d + length_difference(target, genes)
+    }
+
+    fn name(&self) -> &'static str {
+        "weighted"
+    }
+}
+
+impl AbsoluteError {
+    pub fn new(scale: f64) -> Self {
+        AbsoluteError { scale: scale.abs() }
+    }
+}
+
+impl Default for AbsoluteError {
+    fn default() -> Self {
+        AbsoluteError::new(1.0)
+    }
+}
+
+impl<A: Copy + Into<f64>> Fitness<A> for AbsoluteError {
+    fn fitness(&self, target: &[A], genes: &[A]) -> u32 {
+        let d: f64 = target
+            .iter()
+            .zip(genes)
+            .map(|(&t, &g)| (t.into() - g.into()).abs())
+            .sum();
+
+        (d * self.scale).round() as u32 + length_difference(target, genes)
+    }
+
+    fn name(&self) -> &'static str {
+        "absolute-error"
+    }
+}
+
+/// Names accepted by `from_name`.
+pub const FITNESS_NAMES: [&str; 5] = [
+    "hamming",
+    "case-insensitive",
+    "levenshtein",
+    "prefix",
+    "weighted",
+];
+
+/// Builds one of the shipped fitness functions from its name.
+pub fn from_name(name: &str) -> Option<Arc<dyn Fitness>> {
+    match name {
+        "hamming" => Some(Arc::new(Hamming)),
+        "case-insensitive" => Some(Arc::new(CaseInsensitiveHamming)),
+        "levenshtein" => Some(Arc::new(Levenshtein)),
+        "prefix" => Some(Arc::new(LongestCommonPrefix)),
+        "weighted" => Some(Arc::new(WeightedPosition::new())),
+        _ => None,
+    }
+}
+
+// -- Tests: --------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chars(s: &str) -> Vec<char> {
+        s.chars().collect()
+    }
+
+    #[test]
+    fn hamming() {
+        let f = Hamming;
+
+        assert_eq!(f.fitness(&chars("hola"), &chars("hola")), 0);
+        assert_eq!(f.fitness(&chars("hola"), &chars("halo")), 2);
+        assert_eq!(f.fitness(&chars("hola"), &chars("Hola")), 1);
+        assert_eq!(f.fitness(&chars("hola"), &chars("hol")), 1);
+    }
+
+    #[test]
+    fn case_insensitive_hamming() {
+        let f = CaseInsensitiveHamming;
+
+        assert_eq!(f.fitness(&chars("hola"), &chars("HOLA")), 0);
+        assert_eq!(f.fitness(&chars("Ñu"), &chars("ñU")), 0);
+        assert_eq!(f.fitness(&chars("hola"), &chars("HALO")), 2);
+    }
+
+    #[test]
+    fn levenshtein() {
+        let f = Levenshtein;
+
+        assert_eq!(f.fitness(&chars("hola"), &chars("hola")), 0);
+        assert_eq!(f.fitness(&chars("kitten"), &chars("sitting")), 3);
+        assert_eq!(f.fitness(&chars("hola"), &chars("")), 4);
+        assert_eq!(f.fitness(&chars(""), &chars("abc")), 3);
+        assert_eq!(f.fitness(&chars("weasel"), &chars("easel")), 1);
+    }
+
+    #[test]
+    fn longest_common_prefix() {
+        let f = LongestCommonPrefix;
+
+        assert_eq!(f.fitness(&chars("hola"), &chars("hola")), 0);
+        assert_eq!(f.fitness(&chars("hola"), &chars("hoxa")), 2);
+        assert_eq!(f.fitness(&chars("hola"), &chars("xola")), 4);
+        assert_eq!(f.fitness(&chars("hola"), &chars("holas")), 1);
+    }
+
+    #[test]
+    fn weighted_position() {
+        let f = WeightedPosition::new();
+
+        assert_eq!(f.fitness(&chars("hola"), &chars("hola")), 0);
+        assert_eq!(f.fitness(&chars("hola"), &chars("xola")), 4);
+        assert_eq!(f.fitness(&chars("hola"), &chars("holx")), 1);
+
+        let f = WeightedPosition::with_weights(vec![10, 5]);
+        assert_eq!(f.fitness(&chars("hola"), &chars("xxxx")), 17);
+    }
+
+    #[test]
+    fn generic_alleles() {
+        assert_eq!(
+            Hamming.fitness(&[true, true, true], &[true, false, true]),
+            1
+        );
+        assert_eq!(Levenshtein.fitness(&[1u8, 2, 3], &[2u8, 3]), 1);
+
+        let f = AbsoluteError::new(1000.0);
+        assert_eq!(f.fitness(&[0.5, -1.0], &[0.25, -1.0]), 250);
+        assert_eq!(AbsoluteError::default().fitness(&[10u8, 0], &[7u8, 2]), 5);
+    }
+
+    #[test]
+    fn by_name() {
+        for name in FITNESS_NAMES {
+            assert_eq!(from_name(name).unwrap().name(), name);
+        }
+        assert!(from_name("nope").is_none());
+    }
+}
@@ -14,126 +14,185 @@
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
 use delegate::delegate;
+use rand::Rng;
 use std::ops::{Deref, DerefMut};
 
-use crate::libweasel::charset;
+use crate::libweasel::allele::Allele;
 use std::fmt;
 
 pub type GeneList<T> = Vec<Box<T>>;
 
 // -- Classes: ------------------------------------------------------------
+/// A gene holding an allele of type `A`, a char by default.
 #[derive(Clone, Debug)]
-pub struct Gene {
-    data: char,
+pub struct Gene<A: Allele = char> {
+    data: A,
 }
 
+/// A gene that can mutate, either with the rate of its chromosome or with
+/// its own one.  Locked genes never mutate.
 #[derive(Clone, Debug)]
-pub struct MutableGene(Gene);
+pub struct MutableGene<A: Allele = char> {
+    gene: Gene<A>,
+    /// Own mutation rate, `None` uses the one given to `mutate_data`
+    mr: Option<f64>,
+    locked: bool,
+}
 
 // -- Traits: -------------------------------------------------------------
-pub trait GeneExt {
-    fn get(&self) -> char;
-    fn set(&mut self, c: char);
-    fn set_random_data(&mut self);
+/// The type of allele held by a gene.
+pub trait GeneAllele {
+    type Allele: Allele;
+}
+
+pub trait GeneExt<A: Allele> {
+    fn get(&self) -> A;
+    fn set(&mut self, a: A);
+    /// Set the data to a random allele of `domain`.
+    fn set_random_data<R: Rng + ?Sized>(&mut self, domain: &A::Domain, rng: &mut R);
 }
 
-pub trait MutableGeneExt {
-    fn mutate_data(&mut self, mr: f64);
+pub trait MutableGeneExt<A: Allele> {
+    /// Mutate with probability `mr`, or the gene's own rate if it has one.
+    fn mutate_data<R: Rng + ?Sized>(&mut self, mr: f64, domain: &A::Domain, rng: &mut R);
+    fn mr(&self) -> Option<f64>;
+    fn set_mr(&mut self, mr: Option<f64>);
+    fn lock(&mut self);
+    fn unlock(&mut self);
+    fn is_locked(&self) -> bool;
 }
 
-pub trait GeneCreationExt {
-    fn new(c: char) -> Self;
-    fn new_from_random() -> Self;
+pub trait GeneCreationExt<A: Allele> {
+    fn new(a: A) -> Self;
+    fn new_from_random<R: Rng + ?Sized>(domain: &A::Domain, rng: &mut R) -> Self;
 }
 
 // -- Impl. blocks: -------------------------------------------------------
 
-impl DerefMut for Gene {
+impl<A: Allele> DerefMut for Gene<A> {
     fn deref_mut(&mut self) -> &mut Self::Target {
         &mut self.data
     }
 }
 
-impl Deref for Gene {
-    type Target = char;
+impl<A: Allele> Deref for Gene<A> {
+    type Target = A;
 
     fn deref(&self) -> &Self::Target {
         &self.data
     }
 }
 
-impl GeneCreationExt for Gene {
-    fn new(c: char) -> Self {
-        Gene { data: c }
+impl<A: Allele> GeneAllele for Gene<A> {
+    type Allele = A;
+}
+
+impl<A: Allele> GeneAllele for MutableGene<A> {
+    type Allele = A;
+}
+
+impl<A: Allele> GeneCreationExt<A> for Gene<A> {
+    fn new(a: A) -> Self {
+        Gene { data: a }
     }
 
-    fn new_from_random() -> Self {
-        let data = charset::rand_char();
+    fn new_from_random<R: Rng + ?Sized>(domain: &A::Domain, rng: &mut R) -> Self {
+        let data = A::random(domain, rng);
         Gene { data }
     }
 }
 
-impl GeneExt for Gene {
-    fn get(&self) -> char {
-        self.data
+impl<A: Allele> GeneExt<A> for Gene<A> {
+    fn get(&self) -> A {
+        self.data.clone()
     }
-    fn set(&mut self, c: char) {
-        self.data = c;
+    fn set(&mut self, a: A) {
+        self.data = a;
     }
-    fn set_random_data(&mut self) {
+    fn set_random_data<R: Rng + ?Sized>(&mut self, domain: &A::Domain, rng: &mut R) {
         //let oldd = self.get();
-        self.data = charset::rand_char();
+        self.data = A::random(domain, rng);
         // println!("srdd: old:{oldd}, new:{}", self.data);
     }
 }
 
-impl GeneExt for MutableGene {
+impl<A: Allele> GeneExt<A> for MutableGene<A> {
     delegate! {
-        to self.0 {
-          fn get(&self) -> char;
-          fn set(&mut self, c: char);
-          fn set_random_data(&mut self);
+        to self.gene {
+          fn get(&self) -> A;
+          fn set(&mut self, a: A);
+          fn set_random_data<R: Rng + ?Sized>(&mut self, domain: &A::Domain, rng: &mut R);
         }
     }
 }
 
-impl GeneCreationExt for MutableGene {
-    fn new(c: char) -> Self {
-        MutableGene(Gene { data: c })
+impl<A: Allele> GeneCreationExt<A> for MutableGene<A> {
+    fn new(a: A) -> Self {
+        MutableGene {
+            gene: Gene { data: a },
+            mr: None,
+            locked: false,
+        }
     }
 
-    fn new_from_random() -> Self {
-        let data = charset::rand_char();
-        MutableGene(Gene { data })
+    fn new_from_random<R: Rng + ?Sized>(domain: &A::Domain, rng: &mut R) -> Self {
+        MutableGene {
+            gene: Gene::new_from_random(domain, rng),
+            mr: None,
+            locked: false,
+        }
     }
 }
 
-impl fmt::Display for Gene {
+impl<A: Allele> fmt::Display for Gene<A> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         // Escribimos en el formateador 'f' la representación que queremos
-        write!(f, "Gene: {} ", self.data)
+        write!(f, "Gene: {} ", self.data.symbol())
     }
 }
 
-impl fmt::Display for MutableGene {
+impl<A: Allele> fmt::Display for MutableGene<A> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         // Escribimos en el formateador 'f' la representación que queremos
-        write!(f, "MutableGene: {} ", self.get())
+        write!(f, "MutableGene: {} ", self.get().symbol())
     }
 }
 
-impl MutableGeneExt for MutableGene {
-    fn mutate_data(&mut self, mr: f64) {
-        use rand::Rng;
-        let mut rng = rand::rng();
+impl<A: Allele> MutableGeneExt<A> for MutableGene<A> {
+    fn mutate_data<R: Rng + ?Sized>(&mut self, mr: f64, domain: &A::Domain, rng: &mut R) {
+        if self.locked {
+            return;
+        }
+
         // let p = rng.random_range(0.0..=1.0);
         let p: f64 = rng.random();
 
-        if p < mr {
+        if p < self.mr.unwrap_or(mr) {
             //println!("mutate data: p: {p} mr: {mr}");
-            self.set_random_data();
+            let a = self.get().mutate(domain, rng);
+            self.set(a);
         }
     }
+
+    fn mr(&self) -> Option<f64> {
+        self.mr
+    }
+
+    fn set_mr(&mut self, mr: Option<f64>) {
+        self.mr = mr;
+    }
+
+    fn lock(&mut self) {
+        self.locked = true;
+    }
+
+    fn unlock(&mut self) {
+        self.locked = false;
+    }
+
+    fn is_locked(&self) -> bool {
+        self.locked
+    }
 }
 
 impl From<&Gene> for char {
@@ -152,15 +211,87 @@ impl From<&MutableGene> for char {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::libweasel::charset::Charset;
+    use crate::libweasel::rng;
 
     #[test]
     fn mutate_gene() {
+        let mut rng = rng::from_seed(0);
         let mut g = MutableGene::new('a');
-        g.mutate_data(0.8);
+        g.mutate_data(0.8, &Charset::default(), &mut rng);
         let c: char = (&g).into();
         assert!(c != 'a' || g.get() == 'a');
     }
 
+    #[test]
+    fn mutate_gene_is_seeded() {
+        let mut r1 = rng::from_seed(3);
+        let mut r2 = rng::from_seed(3);
+        let mut g1 = MutableGene::new('a');
+        let mut g2 = MutableGene::new('a');
+        let cs = Charset::default();
+
+        for _ in 0..32 {
+            g1.mutate_data(0.5, &cs, &mut r1);
+            g2.mutate_data(0.5, &cs, &mut r2);
+            assert_eq!(g1.get(), g2.get());
+        }
+    }
+
+    #[test]
+    fn locked_gene_never_mutates() {
+        let mut rng = rng::from_seed(4);
+        let cs = Charset::default();
+        let mut g = MutableGene::new('a');
+
+        g.lock();
+        for _ in 0..32 {
+            g.mutate_data(1.0, &cs, &mut rng);
+        }
+        assert_eq!(g.get(), 'a');
+
+        g.unlock();
+        g.mutate_data(1.0, &cs, &mut rng);
+        assert!(!g.is_locked());
+        assert_ne!(g.get(), 'a');
+    }
+
+    #[test]
+    fn own_mutation_rate() {
+        let mut rng = rng::from_seed(4);
+        let mut g = MutableGene::new(true);
+
+        g.set_mr(Some(0.0));
+        g.mutate_data(1.0, &(), &mut rng);
+        assert!(g.get());
+
+        g.set_mr(Some(1.0));
+        g.mutate_data(0.0, &(), &mut rng);
+        assert!(!g.get());
+    }
+
+    #[test]
+    fn random_gene_in_charset() {
+        let mut rng = rng::from_seed(5);
+        let cs = Charset::from_name("dna").unwrap();
+
+        for _ in 0..32 {
+            let g: Gene = Gene::new_from_random(&cs, &mut rng);
+            assert!("ACGT".contains(g.get()));
+        }
+    }
+
+    #[test]
+    fn generic_alleles() {
+        let mut rng = rng::from_seed(6);
+        let mut bit = MutableGene::new(false);
+        bit.mutate_data(1.0, &(), &mut rng);
+        assert!(bit.get());
+
+        let byte: Gene<u8> = Gene::new_from_random(&(10..=20), &mut rng);
+        assert!((10..=20).contains(&*byte));
+    }
+
     #[test]
     fn test_gene_deref() {
         let g = Gene::new('a');
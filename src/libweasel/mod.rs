@@ -13,8 +13,22 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
+pub mod adaptation;
+pub mod allele;
 pub mod arguments;
 pub mod charset;
 pub mod chromosome;
+pub mod config;
 // pub mod evolvingchromosome;
+pub mod distribution;
+pub mod error;
+pub mod fitness;
 pub mod gene;
+pub mod mutation;
+pub mod population;
+pub mod result;
+pub mod rng;
+pub mod selection;
+pub mod termination;
+pub mod theory;
+pub mod trace;
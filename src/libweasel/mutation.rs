@@ -0,0 +1,219 @@
+// Copyright (C) 2025  Antonio-Miguel Corbi Bellot
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use rand::seq::index;
+use rand::{Rng, RngCore};
+use std::sync::Arc;
+
+// -- Traits: -------------------------------------------------------------
+
+/// Which genes of a child mutate.
+pub trait MutationOperator: Send + Sync {
+    /// Indices, in increasing order, of the genes that mutate among `n`,
+    /// `mr` being the mutation rate.
+    fn positions(&self, n: usize, mr: f64, rng: &mut dyn RngCore) -> Vec<usize>;
+    fn name(&self) -> &'static str;
+}
+
+// -- Classes: ------------------------------------------------------------
+
+/// Every gene mutates on its own with probability `mr`, so the number of
+/// mutations is binomial and may be zero.
+#[derive(Clone, Debug, Default)]
+pub struct Bernoulli;
+
+/// Exactly `k` genes, all of them different, whatever `mr` is.
+#[derive(Clone, Debug)]
+pub struct ExactlyK {
+    k: usize,
+}
+
+/// A single gene, whatever `mr` is.
+#[derive(Clone, Debug, Default)]
+pub struct OnePosition;
+
+/// Same distribution as `Bernoulli`, but jumping from one mutation to the
+/// next with a geometric draw, so it takes about `n * mr` draws instead
+/// of `n`.  Much faster for long chromosomes and low rates.
+#[derive(Clone, Debug, Default)]
+pub struct GeometricSkip;
+
+// -- Impl. blocks: -------------------------------------------------------
+
+impl MutationOperator for Bernoulli {
+    fn positions(&self, n: usize, mr: f64, rng: &mut dyn RngCore) -> Vec<usize> {
+        (0..n).filter(|_| rng.random::<f64>() < mr).collect()
+    }
+
+    fn name(&self) -> &'static str {
+        "bernoulli"
+    }
+}
+
+impl ExactlyK {
+    pub fn new(k: usize) -> Self {
+        ExactlyK { k }
+    }
+}
+
+impl MutationOperator for ExactlyK {
+    fn positions(&self, n: usize, _mr: f64, rng: &mut dyn RngCore) -> Vec<usize> {
+        let mut v = index::sample(rng, n, self.k.min(n)).into_vec();
+        v.sort_unstable();
+
+        v
+    }
+
+    fn name(&self) -> &'static str {
+        "exactly"
+    }
+}
+
+impl MutationOperator for OnePosition {
+    fn positions(&self, n: usize, _mr: f64, rng: &mut dyn RngCore) -> Vec<usize> {
+        match n {
+            0 => vec![],
+            _ => vec![rng.random_range(0..n)],
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "one"
+    }
+}
+
+impl MutationOperator for GeometricSkip {
+    fn positions(&self, n: usize, mr: f64, rng: &mut dyn RngCore) -> Vec<usize> {
+        // NaN too, which would otherwise never get past `n`
+        if !mr.is_finite() || mr <= 0.0 {
+            return vec![];
+        }
+        if mr >= 1.0 {
+            return (0..n).collect();
+        }
+
+        // Genes skipped before the next mutation, P(skip = s) = (1 - mr)^s * mr.
+        // `ln_1p` keeps tiny rates from rounding 1 - mr to 1.
+        let ln_q = (-mr).ln_1p();
+        if ln_q == 0.0 || !ln_q.is_finite() {
+            return vec![];
+        }
+        let mut v = vec![];
+        let mut i = 0.0;
+        loop {
+            let u = 1.0 - rng.random::<f64>();
+            i += (u.ln() / ln_q).floor();
+            if i >= n as f64 {
+                return v;
+            }
+            v.push(i as usize);
+            i += 1.0;
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "geometric"
+    }
+}
+
+/// Names accepted by `from_name`.
+pub const MUTATION_NAMES: [&str; 4] = ["bernoulli", "exactly", "one", "geometric"];
+
+/// Builds one of the shipped operators from its name.  The `k` of exactly
+/// can follow a colon, e.g. `exactly:3`, and is 1 otherwise; it can't be 0.
+pub fn from_name(name: &str) -> Option<Arc<dyn MutationOperator>> {
+    let (name, param) = match name.split_once(':') {
+        Some((n, p)) => (n, Some(p)),
+        None => (name, None),
+    };
+
+    match (name, param) {
+        ("bernoulli", None) => Some(Arc::new(Bernoulli)),
+        ("exactly", None) => Some(Arc::new(ExactlyK::new(1))),
+        ("exactly", Some(k)) => match k.parse().ok()? {
+            0 => None,
+            k => Some(Arc::new(ExactlyK::new(k))),
+        },
+        ("one", None) => Some(Arc::new(OnePosition)),
+        ("geometric", None) => Some(Arc::new(GeometricSkip)),
+        _ => None,
+    }
+}
+
+// -- Tests: --------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::libweasel::rng;
+
+    /// Mean number of mutations of `n` genes over 1000 children.
+    fn mean(op: &dyn MutationOperator, n: usize, mr: f64) -> f64 {
+        let mut rng = rng::from_seed(22);
+        let total: usize = (0..1000).map(|_| op.positions(n, mr, &mut rng).len()).sum();
+
+        total as f64 / 1000.0
+    }
+
+    #[test]
+    fn positions_are_sorted_and_in_range() {
+        let mut rng = rng::from_seed(23);
+
+        for name in MUTATION_NAMES {
+            let op = from_name(name).unwrap();
+            for _ in 0..100 {
+                let p = op.positions(50, 0.2, &mut rng);
+                assert!(p.windows(2).all(|w| w[0] < w[1]), "{name}");
+                assert!(p.iter().all(|&i| i < 50), "{name}");
+            }
+            assert!(op.positions(0, 0.5, &mut rng).is_empty());
+        }
+    }
+
+    #[test]
+    fn exact_counts() {
+        let mut rng = rng::from_seed(24);
+
+        assert!((0..100).all(|_| ExactlyK::new(3).positions(10, 0.0, &mut rng).len() == 3));
+        assert_eq!(ExactlyK::new(30).positions(10, 0.0, &mut rng).len(), 10);
+        assert!((0..100).all(|_| OnePosition.positions(10, 1.0, &mut rng).len() == 1));
+    }
+
+    #[test]
+    fn geometric_matches_bernoulli() {
+        for mr in [0.005, 0.05, 0.5] {
+            let b = mean(&Bernoulli, 200, mr);
+            let g = mean(&GeometricSkip, 200, mr);
+            assert!((b - g).abs() < 0.05 * b.max(1.0), "mr {mr}: {b} vs {g}");
+        }
+
+        let mut rng = rng::from_seed(25);
+        assert!(GeometricSkip.positions(100, 0.0, &mut rng).is_empty());
+        assert_eq!(GeometricSkip.positions(100, 1.0, &mut rng).len(), 100);
+        assert!(GeometricSkip.positions(100, f64::NAN, &mut rng).is_empty());
+        assert!(GeometricSkip.positions(100, 1e-17, &mut rng).is_empty());
+        assert!(GeometricSkip.positions(100, 1e-320, &mut rng).is_empty());
+    }
+
+    #[test]
+    fn by_name() {
+        for name in MUTATION_NAMES {
+            assert_eq!(from_name(name).unwrap().name(), name);
+        }
+        assert_eq!(from_name("exactly:4").unwrap().name(), "exactly");
+        assert!(from_name("exactly:x").is_none());
+        assert!(from_name("exactly:0").is_none());
+        assert!(from_name("nope").is_none());
+    }
+}
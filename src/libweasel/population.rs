@@ -0,0 +1,598 @@
+// Copyright (C) 2025  Antonio-Miguel Corbi Bellot
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use crate::libweasel::allele::Allele;
+use crate::libweasel::chromosome::{Chromosome, ChromosomeExt};
+use crate::libweasel::gene::{GeneList, MutableGene};
+use crate::libweasel::result::{EvolutionResult, GenerationRecord};
+use crate::libweasel::rng;
+use crate::libweasel::selection::{Selection, Tournament};
+use crate::libweasel::termination::{
+    CancelFlag, Termination, TerminationMonitor, TerminationReason,
+};
+use rand::Rng;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use signals2::*;
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+
+// -- Classes: ------------------------------------------------------------
+
+/// How two parents are recombined into two children.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Crossover {
+    /// Swap the tails of both parents after a random cut point.
+    OnePoint,
+    /// Swap the segment between two random cut points.
+    TwoPoint,
+    /// Swap every position with probability 1/2.
+    Uniform,
+}
+
+/// A classic generational genetic algorithm.  A whole population of gene
+/// lists evolves at once: parents are picked by a `Selection`, recombined
+/// and mutated, and their children replace the previous generation.
+#[derive(Clone)]
+pub struct Population<T: ChromosomeExt> {
+    // -- Data members: -------------------------------------------------------
+    /// The signal to emit; (generation, best_fit, current_population)
+    pub on_generation: Signal<(u32, u32, Arc<Self>)>,
+    /// Target, fitness, mutation rate and random generator shared by everyone
+    template: Chromosome<T>,
+    /// The gene lists of every individual
+    individuals: Vec<GeneList<T>>,
+    /// Fitness of every individual, same order as `individuals`
+    fitnesses: Vec<u32>,
+    /// How parents are recombined
+    crossover: Crossover,
+    /// Probability of recombining a pair of parents instead of copying them
+    crossover_rate: f64,
+    /// How parents are picked, a 3-way `Tournament` by default
+    selection: Arc<dyn Selection>,
+    /// Generations evolved so far in the current run
+    generation: u32,
+    /// When `evolve` stops
+    termination: Termination,
+    /// Gene lists scored so far in the current run, its starting ones included
+    evaluations: u64,
+    /// Whether `evolve` keeps a record of every generation
+    history: bool,
+}
+
+// -- Impl. blocks: -------------------------------------------------------
+
+impl Crossover {
+    /// Names accepted by `from_name`.
+    pub const NAMES: [&'static str; 3] = ["one-point", "two-point", "uniform"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "one-point" => Some(Crossover::OnePoint),
+            "two-point" => Some(Crossover::TwoPoint),
+            "uniform" => Some(Crossover::Uniform),
+            _ => None,
+        }
+    }
+
+    /// Recombine `a` and `b`.  Cut points never go past the shortest parent,
+    /// whatever is left of the longest one stays in its own child.
+    pub fn apply<G: Clone, R: Rng + ?Sized>(
+        &self,
+        a: &[G],
+        b: &[G],
+        rng: &mut R,
+    ) -> (Vec<G>, Vec<G>) {
+        let mut c1 = a.to_vec();
+        let mut c2 = b.to_vec();
+        let len = a.len().min(b.len());
+
+        if len == 0 {
+            return (c1, c2);
+        }
+
+        match self {
+            Crossover::OnePoint => {
+                let p = rng.random_range(0..=len);
+                c1 = [&a[..p], &b[p..]].concat();
+                c2 = [&b[..p], &a[p..]].concat();
+            }
+            Crossover::TwoPoint => {
+                let p1 = rng.random_range(0..=len);
+                let p2 = rng.random_range(0..=len);
+                let (p1, p2) = (p1.min(p2), p1.max(p2));
+                c1[p1..p2].clone_from_slice(&b[p1..p2]);
+                c2[p1..p2].clone_from_slice(&a[p1..p2]);
+            }
+            Crossover::Uniform => {
+                for i in 0..len {
+                    if rng.random_bool(0.5) {
+                        c1[i] = b[i].clone();
+                        c2[i] = a[i].clone();
+                    }
+                }
+            }
+        }
+
+        (c1, c2)
+    }
+}
+
+impl<T: ChromosomeExt> Population<T> {
+    // -- Methods: ------------------------------------------------------------
+
+    /// A population of `size` random individuals sharing the target,
+    /// fitness, mutation rate and seed of `template`.
+    pub fn new(template: Chromosome<T>, size: usize) -> Self {
+        let mut p = Population {
+            on_generation: Signal::new(),
+            template,
+            individuals: vec![],
+            fitnesses: vec![],
+            crossover: Crossover::OnePoint,
+            crossover_rate: 0.7,
+            selection: Arc::new(Tournament::default()),
+            generation: 0,
+            termination: Termination::default(),
+            evaluations: 0,
+            history: false,
+        };
+        p.create_random_individuals(size.max(1));
+
+        p
+    }
+
+    /// Reseed the population, its individuals are created again.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        let size = self.size();
+        self.template = self.template.with_seed(seed);
+        self.create_random_individuals(size);
+        self
+    }
+
+    pub fn with_crossover(mut self, crossover: Crossover) -> Self {
+        self.crossover = crossover;
+        self
+    }
+
+    /// Recombine pairs of parents with probability `rate`, kept within
+    /// [0, 1].  A NaN rate never recombines.
+    pub fn with_crossover_rate(mut self, rate: f64) -> Self {
+        self.crossover_rate = if rate.is_nan() {
+            0.0
+        } else {
+            rate.clamp(0.0, 1.0)
+        };
+        self
+    }
+
+    pub fn with_selection(mut self, s: Arc<dyn Selection>) -> Self {
+        self.selection = s;
+        self
+    }
+
+    pub fn with_termination(mut self, t: Termination) -> Self {
+        self.termination = t;
+        self
+    }
+
+    /// Make `evolve` return the best fitness of every generation.
+    pub fn with_history(mut self, history: bool) -> Self {
+        self.history = history;
+        self
+    }
+
+    fn create_random_individuals(&mut self, size: usize) {
+        self.individuals = (0..size)
+            .map(|_| self.template.random_gene_list())
+            .collect();
+        self.evaluations = 0;
+        self.evaluate();
+    }
+
+    fn evaluate(&mut self) {
+        #[cfg(feature = "parallel")]
+        let individuals = self.individuals.par_iter();
+        #[cfg(not(feature = "parallel"))]
+        let individuals = self.individuals.iter();
+
+        self.fitnesses = individuals.map(|v| self.template.fitness(v)).collect();
+        self.evaluations += self.fitnesses.len() as u64;
+    }
+
+    pub fn size(&self) -> usize {
+        self.individuals.len()
+    }
+
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.template.seed()
+    }
+
+    pub fn crossover(&self) -> Crossover {
+        self.crossover
+    }
+
+    pub fn crossover_rate(&self) -> f64 {
+        self.crossover_rate
+    }
+
+    pub fn selection_name(&self) -> &'static str {
+        self.selection.name()
+    }
+
+    pub fn fitnesses(&self) -> &[u32] {
+        &self.fitnesses
+    }
+
+    fn best_index(&self) -> usize {
+        (0..self.size())
+            .min_by_key(|&i| self.fitnesses[i])
+            .unwrap_or(0)
+    }
+
+    pub fn best_fitness(&self) -> u32 {
+        self.fitnesses.iter().copied().min().unwrap_or(u32::MAX)
+    }
+
+    pub fn mean_fitness(&self) -> f64 {
+        let total: u64 = self.fitnesses.iter().map(|&f| f as u64).sum();
+
+        total as f64 / self.size().max(1) as f64
+    }
+
+    /// The best individual as a chromosome, e.g. to print it colored.
+    pub fn best(&self) -> Chromosome<T> {
+        let mut c = self.template.clone();
+        if !self.individuals.is_empty() {
+            c.set_gene_list(self.individuals[self.best_index()].clone());
+        }
+
+        c
+    }
+}
+
+impl<A: Allele> Population<MutableGene<A>> {
+    /// Stop `evolve` at the end of the current generation, with
+    /// `TerminationReason::Cancelled`, as `EvolvingChromosome` does.  The
+    /// cancel flag is the template's one, so the copies handed to slots,
+    /// and the template itself, share it.  Requests made between runs are
+    /// dropped when the next one starts.
+    pub fn request_cancel(&self) {
+        self.template.request_cancel();
+    }
+
+    /// The flag raised by `request_cancel`.
+    pub fn cancel_flag(&self) -> CancelFlag {
+        self.template.cancel_flag()
+    }
+
+    /// Replace the population by a new generation of children.  Every pair
+    /// of parents gets its own generator seeded from the template's one, so
+    /// the children are the same whether they are created one after another
+    /// or in parallel.
+    fn next_generation(&mut self) {
+        let n = self.size();
+        let rng = self.template.rng_mut();
+        let parents = self.selection.select_many(&self.fitnesses, n + n % 2, rng);
+        let seeds: Vec<u64> = (0..parents.len() / 2).map(|_| rng.random()).collect();
+
+        let create_pair = |(pair, seed): (&[usize], &u64)| {
+            let mut rng = rng::from_seed(*seed);
+            let (pa, pb) = (&self.individuals[pair[0]], &self.individuals[pair[1]]);
+
+            let (c1, c2) = if rng.random::<f64>() < self.crossover_rate {
+                self.crossover.apply(pa, pb, &mut rng)
+            } else {
+                (pa.clone(), pb.clone())
+            };
+
+            (
+                self.template.mutate_genes(&c1, &mut rng),
+                self.template.mutate_genes(&c2, &mut rng),
+            )
+        };
+
+        #[cfg(feature = "parallel")]
+        let pairs: Vec<_> = parents
+            .par_chunks(2)
+            .zip(seeds.par_iter())
+            .map(create_pair)
+            .collect();
+
+        #[cfg(not(feature = "parallel"))]
+        let pairs: Vec<_> = parents
+            .chunks(2)
+            .zip(seeds.iter())
+            .map(create_pair)
+            .collect();
+
+        let mut next: Vec<GeneList<MutableGene<A>>> = Vec::with_capacity(n + 1);
+        for (c1, c2) in pairs {
+            next.push(c1);
+            next.push(c2);
+        }
+        next.truncate(n);
+
+        self.individuals = next;
+        self.evaluate();
+        self.generation += 1;
+    }
+
+    /// Evolve until one of the `Termination` criteria is met.  The result
+    /// holds the best individual.  Every call starts a new run from the
+    /// current individuals, and a population already holding the target
+    /// isn't evolved any further.
+    pub fn evolve(&mut self) -> EvolutionResult<A> {
+        let cancel = self.cancel_flag();
+        cancel.store(false, Ordering::Relaxed);
+        self.generation = 0;
+        self.evaluations = self.size() as u64;
+
+        let mut monitor = self
+            .termination
+            .monitor()
+            .with_best(self.best_fitness())
+            .with_requests(cancel);
+        let mut history = self.history.then(Vec::new);
+
+        if self.best_fitness() <= self.termination.target_fitness() {
+            return self.result(TerminationReason::TargetReached, &monitor, history);
+        }
+
+        loop {
+            self.next_generation();
+
+            let bf = self.best_fitness();
+            let self_arc = Arc::new(self.clone());
+            self.on_generation
+                .emit(self.generation, bf, self_arc.clone());
+
+            if let Some(h) = history.as_mut() {
+                h.push(GenerationRecord {
+                    generation: self.generation,
+                    best_fitness: bf,
+                    mr: self.template.mr(),
+                });
+            }
+
+            if let Some(reason) = monitor.check(self.generation, bf) {
+                return self.result(reason, &monitor, history);
+            }
+        }
+    }
+
+    /// The result of the run kept by `monitor`, stopped for `reason`.
+    fn result(
+        &self,
+        reason: TerminationReason,
+        monitor: &TerminationMonitor,
+        history: Option<Vec<GenerationRecord>>,
+    ) -> EvolutionResult<A> {
+        let best = self.best();
+
+        EvolutionResult {
+            genes: best.get_alleles(),
+            genes_string: best.get_genes(),
+            best_fitness: self.best_fitness(),
+            generations: self.generation,
+            evaluations: self.evaluations,
+            elapsed: monitor.elapsed(),
+            reason,
+            history,
+        }
+    }
+}
+
+// -- Tests: --------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::libweasel::chromosome::EvolvingChromosome;
+
+    #[test]
+    fn one_point_crossover() {
+        let mut rng = rng::from_seed(0);
+        let a: Vec<char> = "aaaaaa".chars().collect();
+        let b: Vec<char> = "bbbbbb".chars().collect();
+        let (c1, c2) = Crossover::OnePoint.apply(&a, &b, &mut rng);
+        let s1: String = c1.iter().collect();
+        let s2: String = c2.iter().collect();
+        let p = s1.find('b').unwrap_or(6);
+
+        assert_eq!(s1, "a".repeat(p) + &"b".repeat(6 - p));
+        assert_eq!(s2, "b".repeat(p) + &"a".repeat(6 - p));
+    }
+
+    #[test]
+    fn crossover_keeps_genes_in_place() {
+        let mut rng = rng::from_seed(1);
+        let a: Vec<char> = "abcdef".chars().collect();
+        let b: Vec<char> = "ABCDEFGH".chars().collect();
+
+        for x in [Crossover::OnePoint, Crossover::TwoPoint, Crossover::Uniform] {
+            let (c1, c2) = x.apply(&a, &b, &mut rng);
+            assert_eq!(c1.len() + c2.len(), a.len() + b.len());
+            for (i, (g1, g2)) in c1.iter().zip(&c2).enumerate() {
+                assert!(g1.eq_ignore_ascii_case(&a[i]));
+                assert_ne!(g1, g2);
+            }
+        }
+    }
+
+    #[test]
+    fn crossover_by_name() {
+        for name in Crossover::NAMES {
+            assert!(Crossover::from_name(name).is_some());
+        }
+        assert!(Crossover::from_name("nope").is_none());
+    }
+
+    #[test]
+    fn population_finds_target() {
+        let template = EvolvingChromosome::new("hola".into(), 1)
+            .unwrap()
+            .with_mr(0.05);
+        let mut p = Population::new(template, 60)
+            .with_seed(4)
+            .with_crossover(Crossover::Uniform);
+        assert_eq!(p.evolve().reason, TerminationReason::TargetReached);
+        assert_eq!(p.size(), 60);
+        assert_eq!(p.best_fitness(), 0);
+        assert_eq!(p.best().get_genes(), "hola");
+    }
+
+    #[test]
+    fn population_with_selection() {
+        use crate::libweasel::selection::LinearRank;
+
+        let template = EvolvingChromosome::new("hola".into(), 1)
+            .unwrap()
+            .with_mr(0.05);
+        let mut p = Population::new(template, 40)
+            .with_seed(8)
+            .with_selection(Arc::new(LinearRank::new(2.0)));
+        assert_eq!(p.selection_name(), "rank");
+
+        p.evolve();
+
+        assert_eq!(p.best().get_genes(), "hola");
+    }
+
+    #[test]
+    fn population_termination() {
+        // Crossover alone can't bring in characters none of them has.
+        let template = EvolvingChromosome::new("Me thinks it's like a weasel".into(), 1).unwrap();
+        let mut p = Population::new(template, 20)
+            .with_seed(10)
+            .with_termination(Termination::new().with_max_generations(25));
+
+        assert_eq!(p.evolve().reason, TerminationReason::MaxGenerations);
+        assert_eq!(p.generation(), 25);
+    }
+
+    #[test]
+    fn population_of_bits() {
+        let template = EvolvingChromosome::from_alleles(vec![true; 32], 1, ())
+            .unwrap()
+            .with_mr(1.0 / 32.0);
+        let mut p = Population::new(template, 30).with_seed(17);
+
+        assert_eq!(p.evolve().reason, TerminationReason::TargetReached);
+        assert_eq!(p.best().get_genes(), "1".repeat(32));
+    }
+
+    #[test]
+    fn population_result() {
+        let template = EvolvingChromosome::new("hola".into(), 1)
+            .unwrap()
+            .with_mr(0.05);
+        let mut p = Population::new(template, 40)
+            .with_seed(4)
+            .with_history(true);
+        let r = p.evolve();
+
+        assert!(r.target_reached());
+        assert_eq!(r.genes_string, "hola");
+        assert_eq!(r.generations, p.generation());
+        assert_eq!(r.evaluations, 40 * (r.generations as u64 + 1));
+        assert_eq!(r.history.unwrap().len(), r.generations as usize);
+    }
+
+    #[test]
+    fn population_starting_on_target() {
+        // Any individual of 8 bits is at distance 8 or less.
+        let template = EvolvingChromosome::from_alleles(vec![true; 8], 1, ()).unwrap();
+        let mut p = Population::new(template, 10)
+            .with_seed(3)
+            .with_termination(Termination::new().with_target_fitness(8));
+        let r = p.evolve();
+
+        assert_eq!(
+            (r.reason, r.generations),
+            (TerminationReason::TargetReached, 0)
+        );
+        assert_eq!(r.evaluations, 10);
+    }
+
+    #[test]
+    fn population_cancelled() {
+        let template = EvolvingChromosome::new("Me thinks it's like a weasel".into(), 1).unwrap();
+        let mut p = Population::new(template, 20).with_seed(11);
+
+        // Dropped when the run starts.
+        p.request_cancel();
+        p.on_generation.connect(|it, _, population| {
+            if it == 3 {
+                population.request_cancel();
+            }
+        });
+        let r = p.evolve();
+
+        assert_eq!((r.reason, r.generations), (TerminationReason::Cancelled, 3));
+
+        // Not so with a flag of the `Termination`, which is never lowered.
+        let flag = CancelFlag::default();
+        flag.store(true, Ordering::Relaxed);
+        let template = EvolvingChromosome::new("Me thinks it's like a weasel".into(), 1).unwrap();
+        let mut p = Population::new(template, 20)
+            .with_termination(Termination::new().with_cancel_flag(flag.clone()));
+        assert_eq!(p.evolve().reason, TerminationReason::Cancelled);
+        assert!(flag.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn population_crossover_rate() {
+        let template = || EvolvingChromosome::new("hola".into(), 1).unwrap();
+
+        assert_eq!(Population::new(template(), 2).crossover_rate(), 0.7);
+        assert_eq!(
+            Population::new(template(), 2)
+                .with_crossover_rate(1.5)
+                .crossover_rate(),
+            1.0
+        );
+        assert_eq!(
+            Population::new(template(), 2)
+                .with_crossover_rate(-1.0)
+                .crossover_rate(),
+            0.0
+        );
+        assert_eq!(
+            Population::new(template(), 2)
+                .with_crossover_rate(f64::NAN)
+                .crossover_rate(),
+            0.0
+        );
+    }
+
+    #[test]
+    fn population_evolves_again() {
+        let template = EvolvingChromosome::new("Me thinks it's like a weasel".into(), 1).unwrap();
+        let mut p = Population::new(template, 20)
+            .with_seed(12)
+            .with_termination(Termination::new().with_max_generations(10));
+
+        let first = p.evolve();
+        let second = p.evolve();
+
+        assert_eq!((first.generations, second.generations), (10, 10));
+        assert_eq!(second.evaluations, first.evaluations);
+        assert_eq!(p.generation(), 10);
+    }
+}
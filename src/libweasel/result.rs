@@ -0,0 +1,118 @@
+// Copyright (C) 2025  Antonio-Miguel Corbi Bellot
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use crate::libweasel::termination::TerminationReason;
+use std::fmt;
+use std::time::Duration;
+
+// -- Classes: ------------------------------------------------------------
+
+/// What a single generation of a run looked like.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GenerationRecord {
+    pub generation: u32,
+    pub best_fitness: u32,
+    /// Mutation rate the next generation starts with
+    pub mr: f64,
+}
+
+/// A generation of a run, as returned by `step`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Snapshot<A = char> {
+    pub generation: u32,
+    pub best_fitness: u32,
+    /// Mean fitness of the children of this generation
+    pub mean_fitness: f64,
+    /// Fitness of the worst child of this generation
+    pub worst_fitness: u32,
+    /// Mutation rate the next generation starts with
+    pub mr: f64,
+    /// Gene lists scored so far in the run
+    pub evaluations: u64,
+    /// Time since the run started
+    pub elapsed: Duration,
+    /// Alleles of the best genes
+    pub genes: Vec<A>,
+    /// The `Termination` criterion met by this generation, if any
+    pub reason: Option<TerminationReason>,
+}
+
+/// What an evolution ended with, returned by `evolve`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EvolutionResult<A = char> {
+    /// Alleles of the best genes found
+    pub genes: Vec<A>,
+    /// The same genes as printed, e.g. the string for char genes
+    pub genes_string: String,
+    pub best_fitness: u32,
+    /// Generations evolved
+    pub generations: u32,
+    /// Gene lists scored by the fitness function, the starting ones included
+    pub evaluations: u64,
+    pub elapsed: Duration,
+    pub reason: TerminationReason,
+    /// One record per generation, if asked for with `with_history`
+    pub history: Option<Vec<GenerationRecord>>,
+}
+
+// -- Impl. blocks: -------------------------------------------------------
+
+impl<A> EvolutionResult<A> {
+    /// Whether the run ended on the target fitness.
+    pub fn target_reached(&self) -> bool {
+        self.reason == TerminationReason::TargetReached
+    }
+}
+
+impl<A> fmt::Display for EvolutionResult<A> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} ({}) after {} generations, {} evaluations in {:.3}s: {}",
+            self.genes_string,
+            self.best_fitness,
+            self.generations,
+            self.evaluations,
+            self.elapsed.as_secs_f64(),
+            self.reason
+        )
+    }
+}
+
+// -- Tests: --------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display() {
+        let r: EvolutionResult = EvolutionResult {
+            genes: "hola".chars().collect(),
+            genes_string: "hola".into(),
+            best_fitness: 0,
+            generations: 12,
+            evaluations: 1201,
+            elapsed: Duration::from_millis(1500),
+            reason: TerminationReason::TargetReached,
+            history: None,
+        };
+
+        assert!(r.target_reached());
+        assert_eq!(
+            r.to_string(),
+            "hola (0) after 12 generations, 1201 evaluations in 1.500s: target reached"
+        );
+    }
+}
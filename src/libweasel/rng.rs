@@ -0,0 +1,59 @@
+// Copyright (C) 2025  Antonio-Miguel Corbi Bellot
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+
+/// The random number generator used everywhere in the library.  Every
+/// random decision is drawn from one of these so a run can be replayed
+/// from its seed.
+pub type WeaselRng = StdRng;
+
+/// Creates a generator whose whole sequence is fixed by `seed`.
+pub fn from_seed(seed: u64) -> WeaselRng {
+    WeaselRng::seed_from_u64(seed)
+}
+
+/// Picks a fresh seed from the OS entropy source.
+pub fn random_seed() -> u64 {
+    rand::random::<u64>()
+}
+
+// -- Tests: --------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn same_seed_same_sequence() {
+        let mut r1 = from_seed(42);
+        let mut r2 = from_seed(42);
+        let v1: Vec<u64> = (0..16).map(|_| r1.random()).collect();
+        let v2: Vec<u64> = (0..16).map(|_| r2.random()).collect();
+
+        assert_eq!(v1, v2);
+    }
+
+    #[test]
+    fn different_seed_different_sequence() {
+        let mut r1 = from_seed(1);
+        let mut r2 = from_seed(2);
+        let v1: Vec<u64> = (0..16).map(|_| r1.random()).collect();
+        let v2: Vec<u64> = (0..16).map(|_| r2.random()).collect();
+
+        assert_ne!(v1, v2);
+    }
+}
@@ -0,0 +1,379 @@
+// Copyright (C) 2025  Antonio-Miguel Corbi Bellot
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use crate::libweasel::rng::WeaselRng;
+use rand::Rng;
+use std::sync::Arc;
+
+// -- Traits: -------------------------------------------------------------
+
+/// A way of picking individuals by their fitness.  Fitness values are
+/// distances, so lower is better.
+pub trait Selection: Send + Sync {
+    /// Index of one individual chosen among `fitnesses`.
+    fn select(&self, fitnesses: &[u32], rng: &mut WeaselRng) -> usize;
+    fn name(&self) -> &'static str;
+
+    /// Indexes of `n` individuals, chosen independently.
+    fn select_many(&self, fitnesses: &[u32], n: usize, rng: &mut WeaselRng) -> Vec<usize> {
+        (0..n).map(|_| self.select(fitnesses, rng)).collect()
+    }
+
+    /// Indexes of the `n` individuals that survive to the next generation.
+    fn survivors(&self, fitnesses: &[u32], n: usize, rng: &mut WeaselRng) -> Vec<usize> {
+        self.select_many(fitnesses, n, rng)
+    }
+}
+
+// -- Classes: ------------------------------------------------------------
+
+/// Who competes to become the next parents in an evolution strategy.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Replacement {
+    /// (μ,λ): only the λ children, the parents always die.
+    Comma,
+    /// (μ+λ): the μ parents and their λ children.
+    Plus,
+}
+
+/// Always the fittest individual, the first one on ties.  This is what
+/// Dawkins' weasel does with the best child of every generation.  As
+/// survivor selection it keeps the n fittest ones.
+#[derive(Clone, Debug, Default)]
+pub struct Best;
+
+/// The fittest of `k` individuals drawn at random.
+#[derive(Clone, Debug)]
+pub struct Tournament {
+    k: usize,
+}
+
+/// Fitness-proportionate selection.  Distances are turned into weights
+/// with `1 / (1 + f)`, so a perfect individual weighs 1.
+#[derive(Clone, Debug, Default)]
+pub struct Roulette;
+
+/// Linear ranking: the probability of an individual only depends on its
+/// rank.  `pressure` in [1, 2] is how many times the best one is expected
+/// to be picked on average, 1 being no pressure at all.
+#[derive(Clone, Debug)]
+pub struct LinearRank {
+    pressure: f64,
+}
+
+/// Uniform choice among the best `fraction` of the individuals.
+#[derive(Clone, Debug)]
+pub struct Truncation {
+    fraction: f64,
+}
+
+// -- Impl. blocks: -------------------------------------------------------
+
+/// Index drawn from a wheel of cumulative weights.
+pub(crate) fn spin<R: Rng + ?Sized>(cumulative: &[f64], rng: &mut R) -> usize {
+    let total = cumulative.last().copied().unwrap_or(0.0);
+    let x = rng.random::<f64>() * total;
+
+    cumulative
+        .partition_point(|&c| c <= x)
+        .min(cumulative.len() - 1)
+}
+
+pub(crate) fn cumulative(weights: impl Iterator<Item = f64>) -> Vec<f64> {
+    weights
+        .scan(0.0, |acc, w| {
+            *acc += w;
+            Some(*acc)
+        })
+        .collect()
+}
+
+/// Indexes of `fitnesses` sorted from the fittest to the least fit.
+fn ranking(fitnesses: &[u32]) -> Vec<usize> {
+    let mut idx: Vec<usize> = (0..fitnesses.len()).collect();
+    idx.sort_by_key(|&i| fitnesses[i]);
+
+    idx
+}
+
+impl Selection for Best {
+    fn select(&self, fitnesses: &[u32], _rng: &mut WeaselRng) -> usize {
+        (0..fitnesses.len())
+            .min_by_key(|&i| fitnesses[i])
+            .unwrap_or(0)
+    }
+
+    fn name(&self) -> &'static str {
+        "best"
+    }
+
+    fn survivors(&self, fitnesses: &[u32], n: usize, _rng: &mut WeaselRng) -> Vec<usize> {
+        ranking(fitnesses).into_iter().cycle().take(n).collect()
+    }
+}
+
+impl Tournament {
+    pub fn new(k: usize) -> Self {
+        Tournament { k: k.max(1) }
+    }
+}
+
+impl Default for Tournament {
+    fn default() -> Self {
+        Tournament::new(3)
+    }
+}
+
+impl Selection for Tournament {
+    fn select(&self, fitnesses: &[u32], rng: &mut WeaselRng) -> usize {
+        let n = fitnesses.len();
+
+        (0..self.k)
+            .map(|_| rng.random_range(0..n))
+            .min_by_key(|&i| fitnesses[i])
+            .unwrap()
+    }
+
+    fn name(&self) -> &'static str {
+        "tournament"
+    }
+}
+
+impl Roulette {
+    fn wheel(fitnesses: &[u32]) -> Vec<f64> {
+        cumulative(fitnesses.iter().map(|&f| 1.0 / (1.0 + f as f64)))
+    }
+}
+
+impl Selection for Roulette {
+    fn select(&self, fitnesses: &[u32], rng: &mut WeaselRng) -> usize {
+        spin(&Self::wheel(fitnesses), rng)
+    }
+
+    fn name(&self) -> &'static str {
+        "roulette"
+    }
+
+    fn select_many(&self, fitnesses: &[u32], n: usize, rng: &mut WeaselRng) -> Vec<usize> {
+        let wheel = Self::wheel(fitnesses);
+
+        (0..n).map(|_| spin(&wheel, rng)).collect()
+    }
+}
+
+impl LinearRank {
+    pub fn new(pressure: f64) -> Self {
+        LinearRank {
+            pressure: pressure.clamp(1.0, 2.0),
+        }
+    }
+
+    /// Weights of the ranks, the fittest first.
+    fn wheel(&self, n: usize) -> Vec<f64> {
+        let s = self.pressure;
+        let w = |r: usize| {
+            if n < 2 {
+                1.0
+            } else {
+                // r is the rank counting from the least fit one.
+                (2.0 - s) / n as f64 + 2.0 * r as f64 * (s - 1.0) / (n * (n - 1)) as f64
+            }
+        };
+
+        cumulative((0..n).rev().map(w))
+    }
+}
+
+impl Default for LinearRank {
+    fn default() -> Self {
+        LinearRank::new(1.5)
+    }
+}
+
+impl Selection for LinearRank {
+    fn select(&self, fitnesses: &[u32], rng: &mut WeaselRng) -> usize {
+        self.select_many(fitnesses, 1, rng)[0]
+    }
+
+    fn name(&self) -> &'static str {
+        "rank"
+    }
+
+    fn select_many(&self, fitnesses: &[u32], n: usize, rng: &mut WeaselRng) -> Vec<usize> {
+        let ranked = ranking(fitnesses);
+        let wheel = self.wheel(ranked.len());
+
+        (0..n).map(|_| ranked[spin(&wheel, rng)]).collect()
+    }
+}
+
+impl Truncation {
+    pub fn new(fraction: f64) -> Self {
+        Truncation {
+            fraction: fraction.clamp(0.0, 1.0),
+        }
+    }
+
+    fn kept(&self, n: usize) -> usize {
+        ((n as f64 * self.fraction).ceil() as usize).clamp(1, n)
+    }
+}
+
+impl Default for Truncation {
+    fn default() -> Self {
+        Truncation::new(0.5)
+    }
+}
+
+impl Selection for Truncation {
+    fn select(&self, fitnesses: &[u32], rng: &mut WeaselRng) -> usize {
+        self.select_many(fitnesses, 1, rng)[0]
+    }
+
+    fn name(&self) -> &'static str {
+        "truncation"
+    }
+
+    fn select_many(&self, fitnesses: &[u32], n: usize, rng: &mut WeaselRng) -> Vec<usize> {
+        let ranked = ranking(fitnesses);
+        let kept = self.kept(ranked.len());
+
+        (0..n).map(|_| ranked[rng.random_range(0..kept)]).collect()
+    }
+}
+
+impl Replacement {
+    /// Names accepted by `from_name`.
+    pub const NAMES: [&'static str; 2] = ["comma", "plus"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "comma" => Some(Replacement::Comma),
+            "plus" => Some(Replacement::Plus),
+            _ => None,
+        }
+    }
+}
+
+/// Names accepted by `from_name`.
+pub const SELECTION_NAMES: [&str; 5] = ["best", "tournament", "roulette", "rank", "truncation"];
+
+/// Builds one of the shipped selections from its name.  The parameter of
+/// tournament (k), rank (pressure) and truncation (fraction) can follow
+/// a colon, e.g. `tournament:5` or `truncation:0.2`.
+pub fn from_name(name: &str) -> Option<Arc<dyn Selection>> {
+    let (name, param) = match name.split_once(':') {
+        Some((n, p)) => (n, Some(p)),
+        None => (name, None),
+    };
+
+    match (name, param) {
+        ("best", None) => Some(Arc::new(Best)),
+        ("roulette", None) => Some(Arc::new(Roulette)),
+        ("tournament", None) => Some(Arc::new(Tournament::default())),
+        ("tournament", Some(k)) => Some(Arc::new(Tournament::new(k.parse().ok()?))),
+        ("rank", None) => Some(Arc::new(LinearRank::default())),
+        ("rank", Some(s)) => Some(Arc::new(LinearRank::new(s.parse().ok()?))),
+        ("truncation", None) => Some(Arc::new(Truncation::default())),
+        ("truncation", Some(f)) => Some(Arc::new(Truncation::new(f.parse().ok()?))),
+        _ => None,
+    }
+}
+
+// -- Tests: --------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::libweasel::rng;
+
+    const FITNESSES: [u32; 8] = [7, 3, 9, 0, 5, 3, 8, 12];
+
+    /// How many times each index gets picked in 4000 draws.
+    fn histogram(s: &dyn Selection) -> Vec<usize> {
+        let mut rng = rng::from_seed(9);
+        let mut h = vec![0; FITNESSES.len()];
+        for i in s.select_many(&FITNESSES, 4000, &mut rng) {
+            h[i] += 1;
+        }
+
+        h
+    }
+
+    #[test]
+    fn best() {
+        let mut rng = rng::from_seed(0);
+
+        assert_eq!(Best.select(&FITNESSES, &mut rng), 3);
+        assert_eq!(Best.select(&[2, 1, 1], &mut rng), 1);
+    }
+
+    #[test]
+    fn best_survivors() {
+        let mut rng = rng::from_seed(0);
+
+        assert_eq!(Best.survivors(&FITNESSES, 3, &mut rng), vec![3, 1, 5]);
+        assert_eq!(Best.survivors(&[4, 2], 3, &mut rng), vec![1, 0, 1]);
+    }
+
+    #[test]
+    fn tournament() {
+        let h = histogram(&Tournament::new(3));
+
+        assert!(h[3] > h[1]);
+        assert!(h[1] > h[0]);
+        assert!(h[7] < h[0]);
+    }
+
+    #[test]
+    fn roulette() {
+        let h = histogram(&Roulette);
+
+        assert!(h[3] > h[1]);
+        assert!(h[1] > h[7]);
+        assert!(h[7] > 0);
+    }
+
+    #[test]
+    fn linear_rank() {
+        let h = histogram(&LinearRank::new(2.0));
+
+        assert!(h[3] > h[4]);
+        assert!(h[4] > h[2]);
+        assert_eq!(h[7], 0);
+        assert_eq!(histogram(&LinearRank::new(2.0)), h);
+    }
+
+    #[test]
+    fn truncation() {
+        let h = histogram(&Truncation::new(0.25));
+
+        assert!(h[3] > 0);
+        assert_eq!(h[3] + h[1], 4000);
+    }
+
+    #[test]
+    fn by_name() {
+        for name in SELECTION_NAMES {
+            assert_eq!(from_name(name).unwrap().name(), name);
+        }
+        assert_eq!(from_name("tournament:5").unwrap().name(), "tournament");
+        assert!(from_name("tournament:x").is_none());
+        assert!(from_name("best:1").is_none());
+        assert!(from_name("nope").is_none());
+        assert_eq!(Replacement::from_name("comma"), Some(Replacement::Comma));
+        assert_eq!(Replacement::from_name("plus"), Some(Replacement::Plus));
+        assert!(Replacement::from_name("minus").is_none());
+    }
+}
@@ -0,0 +1,273 @@
+// Copyright (C) 2025  Antonio-Miguel Corbi Bellot
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+/// Generations without improvement after which a run gives up, unless
+/// told otherwise.  It keeps runs on unreachable targets from looping
+/// forever.
+pub const DEFAULT_STAGNATION: u32 = 10_000;
+
+/// Shared flag another thread (or a signal slot) can raise to stop a run.
+pub type CancelFlag = Arc<AtomicBool>;
+
+// -- Classes: ------------------------------------------------------------
+
+/// When an evolution stops.  It is checked once per generation, the first
+/// criterion met ends the run.
+#[derive(Clone, Debug)]
+pub struct Termination {
+    /// Stop when the best fitness is this one or better
+    target_fitness: u32,
+    max_generations: Option<u32>,
+    timeout: Option<Duration>,
+    /// Generations in a row without improving the best fitness
+    stagnation: Option<u32>,
+    /// Raised by someone else to stop the run, it is never lowered here
+    cancel: Option<CancelFlag>,
+}
+
+/// The criterion that stopped a run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TerminationReason {
+    TargetReached,
+    MaxGenerations,
+    Timeout,
+    Stagnation,
+    Cancelled,
+}
+
+/// Keeps track of a running evolution against its `Termination`.
+#[derive(Clone, Debug)]
+pub(crate) struct TerminationMonitor {
+    termination: Termination,
+    start: Instant,
+    best: u32,
+    stagnant: u32,
+    /// Raised by `request_cancel` of whoever runs the evolution
+    requested: Option<CancelFlag>,
+}
+
+// -- Impl. blocks: -------------------------------------------------------
+
+impl Default for Termination {
+    fn default() -> Self {
+        Termination {
+            target_fitness: 0,
+            max_generations: None,
+            timeout: None,
+            stagnation: Some(DEFAULT_STAGNATION),
+            cancel: None,
+        }
+    }
+}
+
+impl Termination {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_target_fitness(mut self, f: u32) -> Self {
+        self.target_fitness = f;
+        self
+    }
+
+    pub fn with_max_generations(mut self, n: u32) -> Self {
+        self.max_generations = Some(n);
+        self
+    }
+
+    pub fn with_timeout(mut self, d: Duration) -> Self {
+        self.timeout = Some(d);
+        self
+    }
+
+    /// Stop after `n` generations without improvement, `None` never does.
+    pub fn with_stagnation(mut self, n: Option<u32>) -> Self {
+        self.stagnation = n;
+        self
+    }
+
+    pub fn with_cancel_flag(mut self, flag: CancelFlag) -> Self {
+        self.cancel = Some(flag);
+        self
+    }
+
+    pub fn target_fitness(&self) -> u32 {
+        self.target_fitness
+    }
+
+    pub fn max_generations(&self) -> Option<u32> {
+        self.max_generations
+    }
+
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    pub fn stagnation(&self) -> Option<u32> {
+        self.stagnation
+    }
+
+    /// The flag given by `with_cancel_flag`, if any.
+    pub fn cancel_flag(&self) -> Option<&CancelFlag> {
+        self.cancel.as_ref()
+    }
+
+    pub(crate) fn monitor(&self) -> TerminationMonitor {
+        TerminationMonitor {
+            termination: self.clone(),
+            start: Instant::now(),
+            best: u32::MAX,
+            stagnant: 0,
+            requested: None,
+        }
+    }
+}
+
+impl TerminationMonitor {
+    /// Time since the run started.
+    pub(crate) fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    /// Count stagnation from a run whose starting best fitness is `bf`,
+    /// instead of from its first generation.
+    pub(crate) fn with_best(mut self, bf: u32) -> Self {
+        self.best = bf;
+        self
+    }
+
+    /// Cancel the run on `flag` too, besides the flag of the `Termination`.
+    pub(crate) fn with_requests(mut self, flag: CancelFlag) -> Self {
+        self.requested = Some(flag);
+        self
+    }
+
+    /// Generations in a row without improving the best fitness.
+    pub(crate) fn stagnant(&self) -> u32 {
+        self.stagnant
+    }
+
+    /// Record generation `it` with best fitness `bf`, and tell whether the
+    /// run must stop.
+    pub(crate) fn check(&mut self, it: u32, bf: u32) -> Option<TerminationReason> {
+        let t = &self.termination;
+
+        if bf < self.best {
+            self.best = bf;
+            self.stagnant = 0;
+        } else {
+            self.stagnant += 1;
+        }
+
+        if bf <= t.target_fitness {
+            Some(TerminationReason::TargetReached)
+        } else if [&t.cancel, &self.requested]
+            .into_iter()
+            .flatten()
+            .any(|c| c.load(Ordering::Relaxed))
+        {
+            Some(TerminationReason::Cancelled)
+        } else if t.timeout.is_some_and(|d| self.start.elapsed() >= d) {
+            Some(TerminationReason::Timeout)
+        } else if t.max_generations.is_some_and(|n| it >= n) {
+            Some(TerminationReason::MaxGenerations)
+        } else if t.stagnation.is_some_and(|n| self.stagnant >= n) {
+            Some(TerminationReason::Stagnation)
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for TerminationReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            TerminationReason::TargetReached => "target reached",
+            TerminationReason::MaxGenerations => "maximum number of generations",
+            TerminationReason::Timeout => "timeout",
+            TerminationReason::Stagnation => "no improvement",
+            TerminationReason::Cancelled => "cancelled",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+// -- Tests: --------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn target_reached() {
+        let t = Termination::new().with_target_fitness(2);
+        let mut m = t.monitor();
+
+        assert_eq!(m.check(1, 5), None);
+        assert_eq!(m.check(2, 2), Some(TerminationReason::TargetReached));
+    }
+
+    #[test]
+    fn max_generations() {
+        let t = Termination::new().with_max_generations(3);
+        let mut m = t.monitor();
+
+        assert_eq!(m.check(1, 5), None);
+        assert_eq!(m.check(2, 4), None);
+        assert_eq!(m.check(3, 3), Some(TerminationReason::MaxGenerations));
+    }
+
+    #[test]
+    fn stagnation() {
+        let t = Termination::new().with_stagnation(Some(2));
+        let mut m = t.monitor();
+
+        assert_eq!(m.check(1, 5), None);
+        assert_eq!(m.check(2, 5), None);
+        assert_eq!(m.check(3, 4), None);
+        assert_eq!(m.check(4, 6), None);
+        assert_eq!(m.check(5, 4), Some(TerminationReason::Stagnation));
+    }
+
+    #[test]
+    fn timeout() {
+        let t = Termination::new().with_timeout(Duration::ZERO);
+
+        assert_eq!(t.monitor().check(1, 5), Some(TerminationReason::Timeout));
+    }
+
+    #[test]
+    fn cancelled() {
+        let flag = CancelFlag::default();
+        let t = Termination::new().with_cancel_flag(flag.clone());
+        let mut m = t.monitor();
+
+        assert_eq!(m.check(1, 5), None);
+        flag.store(true, Ordering::Relaxed);
+        assert_eq!(m.check(2, 5), Some(TerminationReason::Cancelled));
+
+        let requested = CancelFlag::default();
+        let mut m = t.monitor().with_requests(requested.clone());
+        flag.store(false, Ordering::Relaxed);
+        assert_eq!(m.check(1, 5), None);
+        requested.store(true, Ordering::Relaxed);
+        assert_eq!(m.check(2, 5), Some(TerminationReason::Cancelled));
+        assert_eq!(m.check(3, 0), Some(TerminationReason::TargetReached));
+    }
+}
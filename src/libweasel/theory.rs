@@ -0,0 +1,129 @@
+// Copyright (C) 2025  Antonio-Miguel Corbi Bellot
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+// -- Classes: ------------------------------------------------------------
+
+/// What to expect from the classic weasel: one parent, `ncopies`
+/// children per generation, genes mutating with probability `mr` to a
+/// char drawn uniformly from a charset of `charset_size` chars, and the
+/// Hamming distance as fitness.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Expectations {
+    len: usize,
+    charset_size: usize,
+    mr: f64,
+    ncopies: u32,
+}
+
+// -- Impl. blocks: -------------------------------------------------------
+
+impl Expectations {
+    pub fn new(len: usize, charset_size: usize, mr: f64, ncopies: u32) -> Self {
+        Expectations {
+            len,
+            charset_size: charset_size.max(1),
+            mr: mr.clamp(0.0, 1.0),
+            ncopies,
+        }
+    }
+
+    /// Fitness of a random string, i.e. of the starting one.
+    pub fn initial_fitness(&self) -> f64 {
+        let k = self.charset_size as f64;
+
+        self.len as f64 * (k - 1.0) / k
+    }
+
+    /// log10 of the random strings a blind search would try, on average,
+    /// before hitting the target: `charset_size ^ len`.
+    pub fn random_search_log10(&self) -> f64 {
+        self.len as f64 * (self.charset_size as f64).log10()
+    }
+
+    /// Probability that a child of a parent at distance `d` is fitter:
+    /// some wrong gene is fixed and no right one is broken.  It's a lower
+    /// bound, as a child breaking one gene and fixing two is fitter too.
+    pub fn improvement_probability(&self, d: usize) -> f64 {
+        let k = self.charset_size as f64;
+        let d = d.min(self.len);
+        let fix = self.mr / k;
+        let brk = self.mr * (k - 1.0) / k;
+
+        (1.0 - (1.0 - fix).powi(d as i32)) * (1.0 - brk).powi((self.len - d) as i32)
+    }
+
+    /// Generations to reach the target, adding up the generations waited
+    /// at every distance from the initial one down to 1.  Infinite if some
+    /// distance can never be improved, e.g. with `mr` 0.
+    pub fn generations(&self) -> f64 {
+        let d0 = self.initial_fitness().round() as usize;
+
+        (1..=d0)
+            .map(|d| {
+                let p = self.improvement_probability(d);
+                1.0 / (1.0 - (1.0 - p).powi(self.ncopies as i32))
+            })
+            .sum()
+    }
+
+    /// Children scored to reach the target.
+    pub fn evaluations(&self) -> f64 {
+        self.generations() * self.ncopies as f64
+    }
+
+    /// The usual rule of thumb: one mutation per child on average.
+    pub fn suggested_mr(&self) -> f64 {
+        1.0 / self.len.max(1) as f64
+    }
+}
+
+// -- Tests: --------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn random_strings() {
+        let e = Expectations::new(28, 27, 0.08, 500);
+
+        assert!((e.initial_fitness() - 28.0 * 26.0 / 27.0).abs() < 1e-9);
+        assert!((e.random_search_log10() - 40.08).abs() < 0.01);
+        assert!((e.suggested_mr() - 1.0 / 28.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn improvements() {
+        let e = Expectations::new(10, 2, 1.0, 1);
+
+        // Bits flipping half the time: every gene must end up right.
+        assert!((e.improvement_probability(10) - (1.0 - 0.5f64.powi(10))).abs() < 1e-12);
+        assert!((e.improvement_probability(1) - 0.5f64.powi(10)).abs() < 1e-12);
+        assert_eq!(
+            Expectations::new(10, 2, 0.0, 1).improvement_probability(5),
+            0.0
+        );
+        assert!(Expectations::new(10, 2, 0.0, 1).generations().is_infinite());
+    }
+
+    #[test]
+    fn more_children_fewer_generations() {
+        let few = Expectations::new(28, 27, 0.04, 50);
+        let many = Expectations::new(28, 27, 0.04, 500);
+
+        assert!(many.generations() < few.generations());
+        assert!(many.evaluations() > few.evaluations());
+        assert!(many.generations() >= 1.0);
+    }
+}
@@ -0,0 +1,254 @@
+// Copyright (C) 2025  Antonio-Miguel Corbi Bellot
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use crate::libweasel::allele::Allele;
+use crate::libweasel::error::{Result, WeaselError};
+use crate::libweasel::result::Snapshot;
+use serde::Serialize;
+use std::io::Write;
+
+// -- Classes: ------------------------------------------------------------
+
+/// One generation of a trace.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct TraceRecord {
+    pub generation: u32,
+    pub best_fitness: u32,
+    pub mean_fitness: f64,
+    pub worst_fitness: u32,
+    pub mr: f64,
+    /// Seconds since the run started
+    pub elapsed: f64,
+    /// The best genes, as printed
+    pub genes: String,
+}
+
+/// How a trace is written.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TraceFormat {
+    /// Aligned columns under a header, for people
+    #[default]
+    Text,
+    /// A JSON object per line
+    Jsonl,
+    /// Comma separated values under a header
+    Csv,
+}
+
+/// Writes the records of a trace to `W`, one per line.
+pub struct TraceWriter<W: Write> {
+    sink: Sink<W>,
+    header_written: bool,
+}
+
+enum Sink<W: Write> {
+    Plain(W, TraceFormat),
+    Csv(Box<csv::Writer<W>>),
+}
+
+// -- Impl. blocks: -------------------------------------------------------
+
+impl<A: Allele> From<&Snapshot<A>> for TraceRecord {
+    fn from(s: &Snapshot<A>) -> Self {
+        let symbols: Vec<String> = s.genes.iter().map(|a| a.symbol()).collect();
+
+        TraceRecord {
+            generation: s.generation,
+            best_fitness: s.best_fitness,
+            mean_fitness: s.mean_fitness,
+            worst_fitness: s.worst_fitness,
+            mr: s.mr,
+            elapsed: s.elapsed.as_secs_f64(),
+            genes: symbols.join(A::SEPARATOR),
+        }
+    }
+}
+
+impl TraceFormat {
+    /// Names accepted by `from_name`.
+    pub const NAMES: [&'static str; 3] = ["text", "jsonl", "csv"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "text" => Some(TraceFormat::Text),
+            "jsonl" => Some(TraceFormat::Jsonl),
+            "csv" => Some(TraceFormat::Csv),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TraceFormat::Text => "text",
+            TraceFormat::Jsonl => "jsonl",
+            TraceFormat::Csv => "csv",
+        }
+    }
+}
+
+impl<W: Write> TraceWriter<W> {
+    pub fn new(out: W, format: TraceFormat) -> Self {
+        let sink = match format {
+            TraceFormat::Csv => Sink::Csv(Box::new(csv::Writer::from_writer(out))),
+            _ => Sink::Plain(out, format),
+        };
+
+        TraceWriter {
+            sink,
+            header_written: false,
+        }
+    }
+
+    pub fn write(&mut self, r: &TraceRecord) -> Result<()> {
+        match &mut self.sink {
+            Sink::Csv(w) => w.serialize(r).map_err(|e| WeaselError::Io(e.to_string()))?,
+            Sink::Plain(w, TraceFormat::Jsonl) => {
+                serde_json::to_writer(&mut *w, r).map_err(|e| WeaselError::Io(e.to_string()))?;
+                writeln!(w).map_err(io_error)?;
+            }
+            Sink::Plain(w, _) => {
+                if !self.header_written {
+                    writeln!(
+                        w,
+                        "{:>10} {:>5} {:>8} {:>5} {:>8} {:>9}  genes",
+                        "generation", "best", "mean", "worst", "mr", "elapsed"
+                    )
+                    .map_err(io_error)?;
+                }
+                writeln!(
+                    w,
+                    "{:>10} {:>5} {:>8.2} {:>5} {:>8.4} {:>8.3}s  {}",
+                    r.generation,
+                    r.best_fitness,
+                    r.mean_fitness,
+                    r.worst_fitness,
+                    r.mr,
+                    r.elapsed,
+                    r.genes
+                )
+                .map_err(io_error)?;
+            }
+        }
+        self.header_written = true;
+
+        Ok(())
+    }
+
+    /// Write out whatever is still buffered.
+    pub fn flush(&mut self) -> Result<()> {
+        match &mut self.sink {
+            Sink::Csv(w) => w.flush(),
+            Sink::Plain(w, _) => w.flush(),
+        }
+        .map_err(io_error)
+    }
+}
+
+fn io_error(e: std::io::Error) -> WeaselError {
+    WeaselError::Io(e.to_string())
+}
+
+// -- Tests: --------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn record(generation: u32, genes: &str) -> TraceRecord {
+        TraceRecord {
+            generation,
+            best_fitness: 3,
+            mean_fitness: 5.5,
+            worst_fitness: 8,
+            mr: 0.05,
+            elapsed: 0.25,
+            genes: genes.into(),
+        }
+    }
+
+    fn written(format: TraceFormat) -> String {
+        let mut out = vec![];
+        let mut w = TraceWriter::new(&mut out, format);
+        w.write(&record(1, "ab, \"c\"")).unwrap();
+        w.write(&record(2, "ñu")).unwrap();
+        w.flush().unwrap();
+        drop(w);
+
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn from_snapshot() {
+        let s = Snapshot {
+            generation: 4,
+            best_fitness: 1,
+            mean_fitness: 2.0,
+            worst_fitness: 3,
+            mr: 0.1,
+            evaluations: 40,
+            elapsed: Duration::from_millis(500),
+            genes: vec![3, 14, 15],
+            reason: None,
+        };
+        let r = TraceRecord::from(&s);
+
+        assert_eq!((r.generation, r.elapsed), (4, 0.5));
+        assert_eq!(r.genes, "3 14 15");
+    }
+
+    #[test]
+    fn jsonl() {
+        let lines: Vec<serde_json::Value> = written(TraceFormat::Jsonl)
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["genes"], "ab, \"c\"");
+        assert_eq!(lines[1]["generation"], 2);
+        assert_eq!(lines[1]["mean_fitness"], 5.5);
+    }
+
+    #[test]
+    fn csv() {
+        let text = written(TraceFormat::Csv);
+        let lines: Vec<&str> = text.lines().collect();
+
+        assert_eq!(
+            lines[0],
+            "generation,best_fitness,mean_fitness,worst_fitness,mr,elapsed,genes"
+        );
+        assert_eq!(lines[1], "1,3,5.5,8,0.05,0.25,\"ab, \"\"c\"\"\"");
+        assert_eq!(lines[2], "2,3,5.5,8,0.05,0.25,ñu");
+    }
+
+    #[test]
+    fn text() {
+        let text = written(TraceFormat::Text);
+        let lines: Vec<&str> = text.lines().collect();
+
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].contains("generation") && lines[0].ends_with("genes"));
+        assert!(lines[2].ends_with("0.250s  ñu"));
+    }
+
+    #[test]
+    fn by_name() {
+        for name in TraceFormat::NAMES {
+            assert_eq!(TraceFormat::from_name(name).unwrap().name(), name);
+        }
+        assert!(TraceFormat::from_name("xml").is_none());
+    }
+}
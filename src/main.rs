@@ -13,92 +13,563 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
-use std::fmt::Arguments;
-
-use colored::Colorize;
+use clap::CommandFactory;
+use clap::error::ErrorKind;
 use signals2::*;
+use std::io;
+use std::process;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
 use weasel_rs::libweasel::{
-    arguments, charset,
-    chromosome::{EvolvingChromosome, StandardChromosome},
-    gene::{Gene, GeneCreationExt, GeneExt},
+    adaptation::MrAdaptation,
+    arguments::{
+        self, BenchArguments, CharsetCommand, Cli, Command, ReplayArguments, SweepArguments,
+    },
+    charset::Charset,
+    chromosome::EvolvingChromosome,
+    distribution,
+    error::Result,
+    fitness,
+    gene::MutableGene,
+    mutation,
+    population::{Crossover, Population},
+    result::EvolutionResult,
+    rng,
+    selection::{self, Replacement, Selection},
+    termination::Termination,
+    theory::Expectations,
+    trace::{TraceFormat, TraceRecord, TraceWriter},
 };
 
-fn check1() {
-    let mut g = Gene::new('a');
-    println!("Hello weasel with gene('a'): {}", g.get());
-    g.set_random_data();
-    println!("Hello weasel with gene(_random_): {}", g.get());
+/// The value in `r`, or the end of the program if it's an error.
+fn or_exit<T>(r: Result<T>) -> T {
+    r.unwrap_or_else(|e| {
+        eprintln!("Error: {e}");
+        process::exit(1)
+    })
+}
 
-    for _ in 1..=32 {
-        println!("rand. char: {}", charset::rand_char());
-    }
+/// The end of the program for a `name` that isn't one of `names`.
+fn unknown(what: &str, name: &str, names: &[&str]) -> ! {
+    eprintln!(
+        "Error: unknown {what} '{name}', use one of: {}",
+        names.join(", ")
+    );
+    process::exit(2)
+}
 
-    println!("ç is allowed char? {}", charset::in_char_set('ç'));
-    println!("! is allowed char? {}", charset::in_char_set('!'));
-    println!("@ is allowed char? {}", charset::in_char_set('@'));
+/// The end of the program for arguments clap can't check on its own.
+fn usage_error(kind: ErrorKind, msg: &str) -> ! {
+    Cli::command().error(kind, msg).exit()
+}
 
-    println!("_ is allowed char? {}", charset::in_char_set('_'));
-    println!("9 is allowed char? {}", charset::in_char_set('9'));
+/// The charset called `name`: a preset, `target` for the chars of
+/// `sentence`, or else the path of a file.  The default one if `None`.
+fn charset_by_name(name: Option<&str>, sentence: &str) -> Result<Charset> {
+    match name {
+        None => Ok(Charset::default()),
+        Some("target") => Charset::from_target(sentence),
+        Some(name) => Charset::from_name(name).map_or_else(|| Charset::from_file(name), Ok),
+    }
+}
 
-    print!("Chromosome: ");
-    let c = StandardChromosome::new("hola".into(), 4);
-    println!("{c}");
+/// The charset named on the command line, with its distribution.
+fn charset_from_args(args: &arguments::Arguments) -> Result<Charset> {
+    let cs = charset_by_name(args.charset(), args.sentence())?;
+
+    match args.distribution() {
+        Some(name) => {
+            let d = distribution::from_name(name, &cs).unwrap_or_else(|| {
+                unknown("distribution", name, &distribution::DISTRIBUTION_NAMES)
+            });
+            Ok(cs.with_distribution(d))
+        }
+        None => Ok(cs),
+    }
 }
 
-fn check_evolve() {
-    let args = arguments::Arguments::from_app_args();
+/// The chromosome described by the command line arguments.
+fn build_chromosome(args: &arguments::Arguments) -> EvolvingChromosome {
     let s = args.sentence().to_owned();
     let nc = args.ncopies();
     let mr = args.mr();
-    let mut ec = EvolvingChromosome::new(s, nc).with_mr(mr);
+    let cs = or_exit(charset_from_args(args));
+    let mut ec = or_exit(EvolvingChromosome::new_with_charset(s, nc, cs))
+        .with_mr(mr)
+        .with_mu(args.mu())
+        .with_elitism(args.elitism())
+        .with_latching(args.latching())
+        .with_mr_adaptation(adaptation_from_args(args))
+        .with_termination(termination_from_args(args));
+
+    if let Some(seed) = args.seed() {
+        ec = ec.with_seed(seed);
+    }
+    if let Some(len) = args.initial_length() {
+        ec = ec.with_variable_length(len);
+    }
+    if let Some(name) = args.fitness() {
+        let f = fitness::from_name(name)
+            .unwrap_or_else(|| unknown("fitness", name, &fitness::FITNESS_NAMES));
+        ec = ec.with_fitness(f);
+    }
+    if let Some(name) = args.selection() {
+        ec = ec.with_selection(selection_from_args(name));
+    }
+    if let Some(name) = args.mutation() {
+        let m = mutation::from_name(name)
+            .unwrap_or_else(|| unknown("mutation", name, &mutation::MUTATION_NAMES));
+        ec = ec.with_mutation(m);
+    }
+    if let Some(name) = args.replacement() {
+        let r = Replacement::from_name(name)
+            .unwrap_or_else(|| unknown("replacement", name, &Replacement::NAMES));
+        ec = ec.with_replacement(r);
+    }
+
+    ec
+}
+
+/// How the mutation rate changes, fixed if not given.
+fn adaptation_from_args(args: &arguments::Arguments) -> MrAdaptation {
+    match args.mr_adaptation() {
+        Some(name) => MrAdaptation::from_name(name)
+            .unwrap_or_else(|| unknown("mr adaptation", name, &MrAdaptation::NAMES)),
+        None => MrAdaptation::Fixed,
+    }
+}
+
+/// When the run described by the command line arguments stops.
+fn termination_from_args(args: &arguments::Arguments) -> Termination {
+    let mut t = Termination::new().with_target_fitness(args.target_fitness());
 
-    ec.on_evolve_iteration.connect(|it, bf, chromosome| {
-        let size = chromosome.size();
+    if let Some(g) = args.max_generations() {
+        t = t.with_max_generations(g);
+    }
+    if let Some(d) = args.timeout() {
+        t = t.with_timeout(d);
+    }
+    if let Some(n) = args.stagnation() {
+        t = t.with_stagnation((n > 0).then_some(n));
+    }
+
+    t
+}
+
+fn selection_from_args(name: &str) -> Arc<dyn Selection> {
+    selection::from_name(name)
+        .unwrap_or_else(|| unknown("selection", name, &selection::SELECTION_NAMES))
+}
+
+/// Print a line about the run, to stderr when stdout is left to the
+/// records of `--encoded` or `--format`.
+fn report(to_stderr: bool, line: &str) {
+    if to_stderr {
+        eprintln!("{line}");
+    } else {
+        println!("{line}");
+    }
+}
+
+/// One `--encoded` record: generation, fitness and genes, tab separated.
+fn print_record(it: u32, bf: u32, chromosome: &EvolvingChromosome) {
+    println!("{it}\t{bf}\t{}", chromosome.get_genes_encoded());
+}
+
+/// The trace format asked for with `--format`, if any.
+fn trace_format(args: &arguments::Arguments) -> Option<TraceFormat> {
+    args.format().map(|name| {
+        TraceFormat::from_name(name).unwrap_or_else(|| unknown("format", name, &TraceFormat::NAMES))
+    })
+}
+
+fn check_evolve(args: &arguments::Arguments) {
+    let mut ec = build_chromosome(args);
+    if let Some(format) = trace_format(args) {
+        return trace_evolve(ec, format);
+    }
+    let encoded = args.encoded();
+    report(encoded, &format!("Seed: {}", ec.seed()));
+
+    let adaptive = ec.mr_adaptation() != MrAdaptation::Fixed;
+    ec.on_evolve_iteration
+        .connect(move |it, bf, mr, chromosome| {
+            let size = chromosome.size();
+            if encoded {
+                print_record(it, bf, &chromosome);
+            } else if adaptive {
+                println!(
+                    "{} ({bf}/{size}) @{it} mr: {mr:.4}",
+                    chromosome.get_genes_colored()
+                );
+            } else {
+                println!(
+                    // "On it.:{it} fitness is {bf} and mr: {}: {}",
+                    // chromosome.mr(),
+                    "{} ({bf}/{size}) @{it}",
+                    chromosome.get_genes_colored()
+                );
+            }
+            //println!("On it.:{it} fitness is {bf}");
+        });
+
+    let result = ec.evolve();
+    print_result(&result, encoded);
+}
+
+/// Evolve `ec` writing the trace of every generation to stdout.
+fn trace_evolve(mut ec: EvolvingChromosome, format: TraceFormat) {
+    report(true, &format!("Seed: {}", ec.seed()));
+
+    let result = Arc::new(Mutex::new(None));
+    let r = result.clone();
+    ec.on_finish
+        .connect(move |res| *r.lock().unwrap() = Some(res));
+
+    let mut trace = TraceWriter::new(io::stdout().lock(), format);
+    for s in ec.evolution() {
+        or_exit(trace.write(&TraceRecord::from(&s)));
+    }
+    or_exit(trace.flush());
+
+    if let Some(result) = result.lock().unwrap().as_ref() {
+        print_result(result, true);
+    }
+}
+
+/// The population of `size` described by the command line arguments.
+fn build_population(args: &arguments::Arguments, size: usize) -> Population<MutableGene> {
+    let mut p =
+        Population::new(build_chromosome(args), size).with_termination(termination_from_args(args));
+
+    if let Some(name) = args.crossover() {
+        let x = Crossover::from_name(name)
+            .unwrap_or_else(|| unknown("crossover", name, &Crossover::NAMES));
+        p = p.with_crossover(x);
+    }
+    if let Some(name) = args.selection() {
+        p = p.with_selection(selection_from_args(name));
+    }
+
+    p
+}
+
+fn check_population(args: &arguments::Arguments, size: usize) {
+    let mut p = build_population(args, size);
+    let format = trace_format(args);
+    let encoded = args.encoded();
+    let to_stderr = encoded || format.is_some();
+    report(to_stderr, &format!("Seed: {}", p.seed()));
+
+    // The trace is written by the slot, and flushed once the run is over.
+    let trace = format.map(|f| Arc::new(Mutex::new(TraceWriter::new(io::stdout(), f))));
+    let t = trace.clone();
+    let start = Instant::now();
+    p.on_generation.connect(move |generation, bf, population| {
+        let best = population.best();
+        if let Some(t) = t.as_ref() {
+            let r = TraceRecord {
+                generation,
+                best_fitness: bf,
+                mean_fitness: population.mean_fitness(),
+                worst_fitness: population.fitnesses().iter().copied().max().unwrap_or(bf),
+                mr: best.mr(),
+                elapsed: start.elapsed().as_secs_f64(),
+                genes: best.get_genes(),
+            };
+            or_exit(t.lock().unwrap().write(&r));
+            return;
+        }
+
+        let size = best.size();
+        if encoded {
+            print_record(generation, bf, &best);
+            return;
+        }
         println!(
-            // "On it.:{it} fitness is {bf} and mr: {}: {}",
-            // chromosome.mr(),
-            "{} ({bf}/{size}) @{it}",
-            chromosome.get_genes_colored()
+            "{} ({bf}/{size}) @{generation} mean: {:.2}",
+            best.get_genes_colored(),
+            population.mean_fitness()
         );
-        //println!("On it.:{it} fitness is {bf}");
     });
 
-    ec.evolve();
+    let result = p.evolve();
+    if let Some(t) = trace {
+        or_exit(t.lock().unwrap().flush());
+    }
+    print_result(&result, to_stderr);
 }
 
-fn check_colors() {
-    let parte1 = "¡Hola".yellow().bold();
-    let parte2 = " Mundo!".cyan().italic();
-    let resultado_format = format!("{}{}", parte1, parte2) + " > FIN.";
-    println!("Resultado format: {resultado_format}");
+fn print_result(result: &EvolutionResult, to_stderr: bool) {
+    report(to_stderr, &format!("Stopped: {}", result.reason));
+    report(
+        to_stderr,
+        &format!(
+            "Generations: {}, evaluations: {}, elapsed: {:.3}s",
+            result.generations,
+            result.evaluations,
+            result.elapsed.as_secs_f64()
+        ),
+    );
+}
+
+/// Evolve the run described by `args`, printing nothing.
+fn evolve_quietly(args: &arguments::Arguments) -> EvolutionResult {
+    match args.population() {
+        Some(size) => build_population(args, size).evolve(),
+        None => build_chromosome(args).evolve(),
+    }
+}
+
+/// The `runs` results of `args`, seeded from `base` onwards.
+fn evolve_runs(args: &arguments::Arguments, base: u64, runs: u32) -> Vec<EvolutionResult> {
+    (0..runs as u64)
+        .map(|i| {
+            let mut args = args.clone();
+            args.set_seed(base.wrapping_add(i));
+            evolve_quietly(&args)
+        })
+        .collect()
+}
+
+fn mean(values: impl Iterator<Item = f64>) -> f64 {
+    let (sum, n) = values.fold((0.0, 0), |(sum, n), v| (sum + v, n + 1));
 
-    let mut cs = "this is red on blue ❤🧡💛💚💙💜".white().on_bright_red();
-    println!("{cs}");
-    println!("{}", "you can also make bold comments".bold());
-    println!("{}", "this is blue".blue());
+    sum / n.max(1) as f64
+}
+
+/// Exit if `args` ask for per-generation output, which `command` doesn't print.
+/// Those can only come from the command line, as `--config` files don't
+/// set them for these commands.
+fn no_trace(args: &arguments::Arguments, command: &str) {
+    if args.encoded() || args.format().is_some() {
+        let mut cli = Cli::command();
+        cli.build();
+        let mut sub = cli.find_subcommand(command).cloned().unwrap_or(cli);
+        sub.error(
+            ErrorKind::ArgumentConflict,
+            format!("{command} prints a summary only, --encoded and --format don't apply"),
+        )
+        .exit();
+    }
+}
+
+fn bench(b: &BenchArguments) {
+    no_trace(b.run(), "bench");
+    let base = b.run().seed().unwrap_or_else(rng::random_seed);
+    println!("Seeds: {base} onwards");
+
+    let results = evolve_runs(b.run(), base, b.runs());
+    let seeds = (0..).map(|i| base.wrapping_add(i));
+    for (seed, r) in seeds.zip(&results) {
+        println!(
+            "{seed}: {} generations, {} evaluations in {:.3}s: {}",
+            r.generations,
+            r.evaluations,
+            r.elapsed.as_secs_f64(),
+            r.reason
+        );
+    }
+
+    let generations = results.iter().map(|r| r.generations);
+    let reached = results.iter().filter(|r| r.target_reached()).count();
+    println!("Target reached: {reached}/{}", results.len());
+    println!(
+        "Generations: mean {:.1}, min {}, max {}",
+        mean(generations.clone().map(f64::from)),
+        generations.clone().min().unwrap_or(0),
+        generations.max().unwrap_or(0)
+    );
+    println!(
+        "Evaluations: mean {:.0}",
+        mean(results.iter().map(|r| r.evaluations as f64))
+    );
     println!(
-        "{}",
-        "you can specify color by string"
-            .color("black")
-            .on_color("yellow")
+        "Elapsed: mean {:.3}s, total {:.3}s",
+        mean(results.iter().map(|r| r.elapsed.as_secs_f64())),
+        results.iter().map(|r| r.elapsed.as_secs_f64()).sum::<f64>()
     );
+}
+
+fn sweep(s: &SweepArguments) {
+    no_trace(s.run(), "sweep");
+    let base = s.run().seed().unwrap_or_else(rng::random_seed);
+    println!("Seeds: {base} onwards");
     println!(
-        "{}",
-        format!("{} {} !", "it".green(), "works".blue().bold())
+        "{:>8} {:>8} {:>12} {:>12} {:>10}",
+        "mr", "reached", "generations", "evaluations", "elapsed"
     );
+
+    for mr in s.rates() {
+        let mut args = s.run().clone();
+        args.set_mr(mr);
+        let results = evolve_runs(&args, base, s.runs());
+        let reached = results.iter().filter(|r| r.target_reached()).count();
+
+        println!(
+            "{mr:>8.4} {:>8} {:>12.1} {:>12.0} {:>9.3}s",
+            format!("{reached}/{}", results.len()),
+            mean(results.iter().map(|r| r.generations as f64)),
+            mean(results.iter().map(|r| r.evaluations as f64)),
+            mean(results.iter().map(|r| r.elapsed.as_secs_f64()))
+        );
+    }
 }
 
-fn check_args() {
-    let args = arguments::Arguments::from_app_args();
+fn replay(r: &ReplayArguments) {
+    let args = r.run();
+    if args.seed().is_none() {
+        usage_error(
+            ErrorKind::MissingRequiredArgument,
+            "replay needs the --seed of the run",
+        );
+    }
+    if args.population().is_some() {
+        usage_error(
+            ErrorKind::ArgumentConflict,
+            "replay works on a single chromosome, not on a --population",
+        );
+    }
+
+    let mut ec = build_chromosome(args);
+    let encoded = args.encoded();
+    let mut trace = trace_format(args).map(|f| TraceWriter::new(io::stdout().lock(), f));
+    let to_stderr = encoded || trace.is_some();
+    report(to_stderr, &format!("Seed: {}", ec.seed()));
+
+    let last = r.to_generation().unwrap_or(u32::MAX);
+    loop {
+        let s = ec.step();
+        let shown = s.generation >= r.from_generation();
+        if let Some(t) = trace.as_mut().filter(|_| shown) {
+            or_exit(t.write(&TraceRecord::from(&s)));
+        } else if shown && encoded {
+            print_record(s.generation, s.best_fitness, &ec);
+        } else if shown {
+            println!(
+                "{} ({}/{}) @{}",
+                ec.get_genes_colored(),
+                s.best_fitness,
+                ec.size(),
+                s.generation
+            );
+        }
+
+        if let Some(reason) = s.reason {
+            report(to_stderr, &format!("Stopped: {reason}"));
+            break;
+        }
+        if s.generation >= last {
+            report(to_stderr, &format!("Stopped: generation {last}"));
+            break;
+        }
+    }
+    if let Some(mut t) = trace {
+        or_exit(t.flush());
+    }
+}
+
+fn list_charsets() {
+    for name in Charset::NAMES {
+        let cs = Charset::from_name(name).expect("presets always build");
+        if cs.len() <= 256 {
+            println!(
+                "{name} ({} chars): {}",
+                cs.len(),
+                String::from_iter(cs.chars())
+            );
+        } else {
+            println!("{name} ({} chars)", cs.len());
+        }
+    }
+}
+
+/// Whether `sentence` could be a target with `charset`.  It exits with
+/// status 1 if not, like a run would.
+fn validate_charset(sentence: &str, charset: Option<&str>) {
+    let cs = or_exit(charset_by_name(charset, sentence));
+    let len = cs.len();
+
+    or_exit(EvolvingChromosome::new_with_charset(sentence.into(), 1, cs));
+    println!("Valid: every char of the sentence is in the charset ({len} chars)");
+}
+
+fn print_args(args: &arguments::Arguments) {
+    if let Some(path) = args.config() {
+        println!("Config: {}", path.display());
+    }
     println!("Sentence: {}", args.sentence());
     println!("Mutation rate: {}", args.mr());
     println!("Number of copies: {}", args.ncopies());
     println!("Encoded output?: {}", args.encoded());
+    println!("Format: {:?}", args.format());
+    println!("Seed: {:?}", args.seed());
+    println!("Fitness: {:?}", args.fitness());
+    println!("Charset: {:?}", args.charset());
+    println!("Distribution: {:?}", args.distribution());
+    println!("Initial length: {:?}", args.initial_length());
+    println!("Population: {:?}", args.population());
+    println!("Crossover: {:?}", args.crossover());
+    println!("Selection: {:?}", args.selection());
+    println!("Mu: {}", args.mu());
+    println!("Replacement: {:?}", args.replacement());
+    println!("Elitism: {}", args.elitism());
+    println!("Latching: {}", args.latching());
+    println!("Mr adaptation: {:?}", args.mr_adaptation());
+    println!("Mutation: {:?}", args.mutation());
+    println!("Max. generations: {:?}", args.max_generations());
+    println!("Timeout: {:?}", args.timeout());
+    println!("Target fitness: {}", args.target_fitness());
+    println!("Stagnation: {:?}", args.stagnation());
+}
+
+fn explain(args: &arguments::Arguments) {
+    print_args(args);
+
+    let ec = build_chromosome(args);
+    let len = ec.target_alleles().len();
+    let e = Expectations::new(len, ec.charset().len(), args.mr(), args.ncopies());
+
+    println!();
+    println!("For one parent, uniform mutation and the Hamming distance:");
+    println!("Initial fitness: {:.1}/{len}", e.initial_fitness());
+    println!(
+        "Blind search: about 10^{:.1} random strings",
+        e.random_search_log10()
+    );
+    println!("Expected generations: {:.0}", e.generations());
+    println!("Expected evaluations: {:.0}", e.evaluations());
+    println!(
+        "Suggested mutation rate: {:.4} (1/length)",
+        e.suggested_mr()
+    );
+
+    let classic = args.population().is_none()
+        && args.mu() == 1
+        && args.initial_length().is_none()
+        && args.fitness().is_none_or(|f| f == "hamming")
+        && args.distribution().is_none_or(|d| d == "uniform")
+        && args.mr_adaptation().is_none_or(|a| a == "fixed")
+        && args.mutation().is_none_or(|m| m == "bernoulli")
+        && !args.latching();
+    if !classic {
+        println!("This run is set up otherwise, so take these as a rough guide.");
+    }
 }
 
 fn main() {
-    // check1();
-    // check_colors();
-    // check_args();
-    check_evolve();
+    match Cli::from_app_args().into_command() {
+        Command::Run(args) => match args.population() {
+            Some(size) => check_population(&args, size),
+            None => check_evolve(&args),
+        },
+        Command::Bench(b) => bench(&b),
+        Command::Sweep(s) => sweep(&s),
+        Command::Replay(r) => replay(&r),
+        Command::Charset(CharsetCommand::List) => list_charsets(),
+        Command::Charset(CharsetCommand::Validate { sentence, charset }) => {
+            validate_charset(&sentence, charset.as_deref())
+        }
+        Command::Explain(args) => explain(&args),
+    }
 }